@@ -0,0 +1,8 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- folds hamza forms, ta marbuta and alef maqsura and strips tashkeel and tatweel
+CREATE OR REPLACE FUNCTION normalize_arabic(input TEXT) RETURNS TEXT AS $$
+  SELECT lower(translate(input, 'أإآٱةىًٌٍَُِّْٰٕٖٜٟٓٔٗ٘ٙٚٛٝٞـ', 'ااااهي'))
+$$ LANGUAGE SQL IMMUTABLE STRICT PARALLEL SAFE;
+
+CREATE INDEX IF NOT EXISTS member_name_search_idx ON member USING GIN (normalize_arabic(name) gin_trgm_ops);
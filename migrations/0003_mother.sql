@@ -0,0 +1,3 @@
+ALTER TABLE member ADD COLUMN IF NOT EXISTS mother_id UUID REFERENCES member(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS member_mother_idx ON member(mother_id);
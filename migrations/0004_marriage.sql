@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS marriage (
+  id UUID PRIMARY KEY NOT NULL,
+  husband_id UUID NOT NULL,
+  wife_id UUID NOT NULL,
+  start_date DATE,
+  end_date DATE,
+  status VARCHAR(10) NOT NULL DEFAULT 'married' CHECK (status IN ('married','divorced','widowed')),
+  CHECK (husband_id <> wife_id),
+  FOREIGN KEY(husband_id) REFERENCES member(id) ON DELETE CASCADE,
+  FOREIGN KEY(wife_id) REFERENCES member(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS marriage_husband_idx ON marriage(husband_id);
+CREATE INDEX IF NOT EXISTS marriage_wife_idx ON marriage(wife_id);
@@ -0,0 +1,5 @@
+ALTER TABLE member
+  ADD COLUMN IF NOT EXISTS birth_date DATE,
+  ADD COLUMN IF NOT EXISTS birth_precision VARCHAR(12) CHECK (birth_precision IN ('exact','year','approximate','before','after')),
+  ADD COLUMN IF NOT EXISTS death_date DATE,
+  ADD COLUMN IF NOT EXISTS death_precision VARCHAR(12) CHECK (death_precision IN ('exact','year','approximate','before','after'));
@@ -0,0 +1,14 @@
+ALTER TABLE member
+  ALTER COLUMN name TYPE VARCHAR(150),
+  ADD COLUMN IF NOT EXISTS kunya VARCHAR(100),
+  ADD COLUMN IF NOT EXISTS laqab VARCHAR(100),
+  ADD COLUMN IF NOT EXISTS nisba VARCHAR(100),
+  ADD COLUMN IF NOT EXISTS alt_names TEXT[] NOT NULL DEFAULT '{}';
+
+-- every name a member is known by besides the main one, normalized for search
+CREATE OR REPLACE FUNCTION member_other_names(kunya TEXT, laqab TEXT, nisba TEXT, alt_names TEXT[]) RETURNS TEXT AS $$
+  SELECT normalize_arabic(concat_ws(' ', kunya, laqab, nisba, array_to_string(alt_names, ' ')))
+$$ LANGUAGE SQL IMMUTABLE PARALLEL SAFE;
+
+CREATE INDEX IF NOT EXISTS member_other_names_search_idx ON member
+  USING GIN (member_other_names(kunya, laqab, nisba, alt_names) gin_trgm_ops);
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS sessions (
+  id UUID PRIMARY KEY NOT NULL,
+  user_id UUID NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  expires_at TIMESTAMP NOT NULL,
+  FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS sessions_user_idx ON sessions(user_id);
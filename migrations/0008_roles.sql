@@ -0,0 +1,12 @@
+ALTER TABLE users ADD COLUMN IF NOT EXISTS role VARCHAR(20) NOT NULL DEFAULT 'viewer'
+  CHECK (role IN ('viewer','editor','branch_admin','super_admin'));
+
+CREATE TABLE IF NOT EXISTS grants (
+  user_id UUID NOT NULL,
+  root_id UUID NOT NULL,
+  PRIMARY KEY(user_id,root_id),
+  FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE,
+  FOREIGN KEY(root_id) REFERENCES member(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS grants_root_idx ON grants(root_id);
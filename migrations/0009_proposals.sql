@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS proposals (
+  id UUID PRIMARY KEY NOT NULL,
+  author_id UUID NOT NULL,
+  updates JSONB NOT NULL,
+  status VARCHAR(10) NOT NULL DEFAULT 'pending' CHECK (status IN ('pending','approved','rejected')),
+  created_at TIMESTAMP NOT NULL,
+  reviewer_id UUID,
+  reviewed_at TIMESTAMP,
+  FOREIGN KEY(author_id) REFERENCES users(id) ON DELETE CASCADE,
+  FOREIGN KEY(reviewer_id) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS proposals_pending_idx ON proposals(created_at) WHERE status = 'pending';
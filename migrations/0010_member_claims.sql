@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS member_claims (
+  id UUID PRIMARY KEY NOT NULL,
+  user_id UUID NOT NULL,
+  member_id UUID NOT NULL,
+  status VARCHAR(10) NOT NULL DEFAULT 'pending' CHECK (status IN ('pending','approved','rejected')),
+  created_at TIMESTAMP NOT NULL,
+  reviewer_id UUID,
+  reviewed_at TIMESTAMP,
+  FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE,
+  FOREIGN KEY(member_id) REFERENCES member(id) ON DELETE CASCADE,
+  FOREIGN KEY(reviewer_id) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS member_claims_pending_idx ON member_claims(user_id,member_id) WHERE status = 'pending';
+CREATE UNIQUE INDEX IF NOT EXISTS users_member_idx ON users(member_id) WHERE member_id IS NOT NULL;
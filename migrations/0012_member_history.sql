@@ -0,0 +1,24 @@
+CREATE TABLE IF NOT EXISTS member_history (
+  id UUID PRIMARY KEY NOT NULL,
+  batch_id UUID NOT NULL,
+  member_id UUID NOT NULL,
+  parent_id UUID,
+  action VARCHAR(10) NOT NULL CHECK (action IN ('create','update','delete')),
+  author_id UUID,
+  changed_at TIMESTAMP NOT NULL,
+  before JSONB,
+  after JSONB
+);
+
+CREATE INDEX IF NOT EXISTS member_history_member_idx ON member_history(member_id);
+CREATE INDEX IF NOT EXISTS member_history_parent_idx ON member_history(parent_id);
+CREATE INDEX IF NOT EXISTS member_history_batch_idx ON member_history(batch_id);
+
+CREATE OR REPLACE FUNCTION member_history_append_only() RETURNS TRIGGER AS $$
+BEGIN
+  RAISE EXCEPTION 'member_history is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER member_history_append_only BEFORE UPDATE OR DELETE ON member_history
+FOR EACH ROW EXECUTE FUNCTION member_history_append_only();
@@ -0,0 +1,11 @@
+ALTER TABLE member ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP;
+ALTER TABLE member ADD COLUMN IF NOT EXISTS deletion_id UUID;
+ALTER TABLE member ADD COLUMN IF NOT EXISTS deleted_by UUID REFERENCES users(id) ON DELETE SET NULL;
+
+ALTER TABLE member DROP CONSTRAINT IF EXISTS member_parent_id_name_key;
+CREATE UNIQUE INDEX IF NOT EXISTS member_parent_name_idx ON member(parent_id,name) WHERE deleted_at IS NULL;
+CREATE INDEX IF NOT EXISTS member_deletion_idx ON member(deletion_id) WHERE deletion_id IS NOT NULL;
+
+ALTER TABLE member_history DROP CONSTRAINT IF EXISTS member_history_action_check;
+ALTER TABLE member_history ADD CONSTRAINT member_history_action_check
+  CHECK (action IN ('create','update','delete','restore','purge'));
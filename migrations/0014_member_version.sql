@@ -0,0 +1,11 @@
+ALTER TABLE member ADD COLUMN IF NOT EXISTS version INT NOT NULL DEFAULT 1;
+
+-- pending proposals were stored before member versions were tracked
+UPDATE proposals SET updates = jsonb_set(jsonb_set(updates,
+  '{deleted_members}', COALESCE((
+    SELECT jsonb_agg(jsonb_build_array(x, 1)) FROM jsonb_array_elements(updates->'deleted_members') x
+  ), '[]'::jsonb)),
+  '{updated_members}', COALESCE((
+    SELECT jsonb_agg(x || '{"version": 1}'::jsonb) FROM jsonb_array_elements(updates->'updated_members') x
+  ), '[]'::jsonb))
+WHERE status = 'pending';
@@ -0,0 +1,3 @@
+ALTER TABLE member_history DROP CONSTRAINT IF EXISTS member_history_action_check;
+ALTER TABLE member_history ADD CONSTRAINT member_history_action_check
+  CHECK (action IN ('create','update','delete','restore','purge','merge'));
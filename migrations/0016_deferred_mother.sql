@@ -0,0 +1 @@
+ALTER TABLE member ALTER CONSTRAINT member_mother_id_fkey DEFERRABLE INITIALLY DEFERRED;
@@ -0,0 +1,414 @@
+use std::str::FromStr;
+
+use crate::{
+    auth::get_current_user,
+    db::users::{Role, UserInfo},
+};
+use leptos::*;
+use leptos_router::use_navigate;
+use uuid::Uuid;
+
+const USERNAME_LENGTH: std::ops::RangeInclusive<usize> = 3..=100;
+const PASSWORD_MIN_LENGTH: usize = 8;
+
+pub fn validate_username(username: &str) -> Result<(), String> {
+    let length = username.chars().count();
+    if !USERNAME_LENGTH.contains(&length) {
+        return Err(format!(
+            "اسم المستخدم يجب ان يكون بين {} و {} حرفا",
+            USERNAME_LENGTH.start(),
+            USERNAME_LENGTH.end()
+        ));
+    }
+    if !username
+        .chars()
+        .all(|x| x.is_alphanumeric() || matches!(x, '_' | '-' | '.'))
+    {
+        return Err("اسم المستخدم يقبل الحروف والارقام و _ - . فقط".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_password(password: &str) -> Result<(), String> {
+    if password.chars().count() < PASSWORD_MIN_LENGTH {
+        return Err(format!(
+            "كلمة المرور يجب ان تكون {PASSWORD_MIN_LENGTH} احرف على الاقل"
+        ));
+    }
+    Ok(())
+}
+
+fn error_message(err: ServerFnError) -> String {
+    match err {
+        ServerFnError::ServerError(message) => message,
+        _ => "حدث خطأ غير متوقع".to_string(),
+    }
+}
+
+#[cfg(feature = "ssr")]
+fn invalid(message: String) -> ServerFnError {
+    ServerFnError::ServerError(message)
+}
+
+#[cfg(feature = "ssr")]
+async fn require_super_admin() -> Result<UserInfo, ServerFnError> {
+    let user = crate::auth::require_user().await?;
+    if user.role != Role::SuperAdmin {
+        return Err(ServerFnError::ServerError("لا تملك الصلاحية".to_string()));
+    }
+    Ok(user)
+}
+
+#[cfg(feature = "ssr")]
+async fn username_taken(username: &str) -> Result<bool, ServerFnError> {
+    use crate::db::{users, Pool, Postgres};
+    let pool = expect_context::<Pool<Postgres>>();
+    let Ok(user) = users::find(&pool, username).await else {
+        return Err(ServerFnError::ServerError("error reading user".to_string()));
+    };
+    Ok(user.is_some())
+}
+
+#[server(encoding = "Cbor")]
+async fn register(username: String, password: String) -> Result<UserInfo, ServerFnError> {
+    use crate::db::{users, users::User, Pool, Postgres};
+    let pool = expect_context::<Pool<Postgres>>();
+    let username = username.trim().to_string();
+    validate_username(&username).map_err(invalid)?;
+    validate_password(&password).map_err(invalid)?;
+    if username_taken(&username).await? {
+        return Err(ServerFnError::ServerError(
+            "اسم المستخدم مستخدم من قبل".to_string(),
+        ));
+    }
+    let user = User {
+        id: Uuid::new_v4(),
+        username,
+        password,
+        member_id: None,
+        role: Role::default(),
+        active: true,
+    };
+    let mut info = user.info();
+    let Ok(role) = users::create(user, &pool).await else {
+        return Err(ServerFnError::ServerError(
+            "error creating user".to_string(),
+        ));
+    };
+    info.role = role;
+    crate::auth::start_session(&info).await?;
+    Ok(info)
+}
+
+#[server(encoding = "Cbor")]
+async fn update_profile(
+    username: String,
+    current_password: String,
+    new_password: String,
+) -> Result<(), ServerFnError> {
+    use crate::db::{users, Pool, Postgres};
+    let user = crate::auth::require_user().await?;
+    let pool = expect_context::<Pool<Postgres>>();
+    let Ok(stored) = users::read(&pool, user.id).await else {
+        return Err(ServerFnError::ServerError("error reading user".to_string()));
+    };
+    if !users::verify_password(&current_password, &stored.password) {
+        return Err(ServerFnError::ServerError(
+            "كلمة المرور الحالية غير صحيحة".to_string(),
+        ));
+    }
+    let username = username.trim().to_string();
+    if username != user.username {
+        validate_username(&username).map_err(invalid)?;
+        if username_taken(&username).await? {
+            return Err(ServerFnError::ServerError(
+                "اسم المستخدم مستخدم من قبل".to_string(),
+            ));
+        }
+        let Ok(_) = users::set_username(&pool, user.id, &username).await else {
+            return Err(ServerFnError::ServerError(
+                "error updating username".to_string(),
+            ));
+        };
+    }
+    if !new_password.is_empty() {
+        validate_password(&new_password).map_err(invalid)?;
+        let Ok(_) = users::set_password(&pool, user.id, &new_password).await else {
+            return Err(ServerFnError::ServerError(
+                "error updating password".to_string(),
+            ));
+        };
+    }
+    Ok(())
+}
+
+#[server(encoding = "Cbor")]
+async fn get_users() -> Result<Vec<UserInfo>, ServerFnError> {
+    use crate::db::{users, Pool, Postgres};
+    require_super_admin().await?;
+    let pool = expect_context::<Pool<Postgres>>();
+    let Ok(users) = users::list(&pool).await else {
+        return Err(ServerFnError::ServerError(
+            "error reading users".to_string(),
+        ));
+    };
+    Ok(users)
+}
+
+#[server(encoding = "Cbor")]
+async fn set_user_role(id: Uuid, role: Role) -> Result<(), ServerFnError> {
+    use crate::db::{users, Pool, Postgres};
+    let admin = require_super_admin().await?;
+    if admin.id == id {
+        return Err(ServerFnError::ServerError(
+            "لا يمكنك تغيير دورك بنفسك".to_string(),
+        ));
+    }
+    let pool = expect_context::<Pool<Postgres>>();
+    let Ok(_) = users::set_role(&pool, id, role).await else {
+        return Err(ServerFnError::ServerError(
+            "error updating role".to_string(),
+        ));
+    };
+    Ok(())
+}
+
+#[server(encoding = "Cbor")]
+async fn set_user_active(id: Uuid, active: bool) -> Result<(), ServerFnError> {
+    use crate::db::{users, Pool, Postgres};
+    let admin = require_super_admin().await?;
+    if admin.id == id {
+        return Err(ServerFnError::ServerError(
+            "لا يمكنك تعطيل حسابك بنفسك".to_string(),
+        ));
+    }
+    let pool = expect_context::<Pool<Postgres>>();
+    let Ok(_) = users::set_active(&pool, id, active).await else {
+        return Err(ServerFnError::ServerError(
+            "error updating user".to_string(),
+        ));
+    };
+    Ok(())
+}
+
+#[component]
+fn FormInput(
+    placeholder: &'static str,
+    value: RwSignal<String>,
+    #[prop(optional)] password: bool,
+) -> impl IntoView {
+    view! {
+        <input
+            type=if password { "password" } else { "text" }
+            class="placeholder:text-center placeholder-gray-400 bg-gray-800 border-gray-500 hover:border-gray-800 text-center border-2 p-2 text-2xl rounded-lg w-96"
+            placeholder=placeholder
+            prop:value=move || value.get()
+            on:input=move |ev| value.set(event_target_value(&ev))
+        />
+    }
+}
+
+#[component]
+pub fn Register() -> impl IntoView {
+    let username = RwSignal::new(String::new());
+    let password = RwSignal::new(String::new());
+    let confirm = RwSignal::new(String::new());
+    let error = RwSignal::new(None::<String>);
+    let navigate = use_navigate();
+
+    let submit = move |_| {
+        let username = username.get_untracked().trim().to_string();
+        let password = password.get_untracked();
+        let checked = validate_username(&username)
+            .and_then(|_| validate_password(&password))
+            .and_then(|_| {
+                (password == confirm.get_untracked())
+                    .then_some(())
+                    .ok_or("كلمتا المرور غير متطابقتين".to_string())
+            });
+        if let Err(message) = checked {
+            error.set(Some(message));
+            return;
+        }
+        let navigate = navigate.clone();
+        spawn_local(async move {
+            match register(username, password).await {
+                Ok(_) => navigate("/", Default::default()),
+                Err(err) => error.set(Some(error_message(err))),
+            }
+        });
+    };
+
+    view! {
+        <section class="grid justify-items-center gap-5 overflow-auto">
+            <h1 class="text-center m-5 text-3xl">انشاء حساب</h1>
+            <FormInput placeholder="اسم المستخدم" value=username/>
+            <FormInput placeholder="كلمة المرور" value=password password=true/>
+            <FormInput placeholder="تاكيد كلمة المرور" value=confirm password=true/>
+            <p class="text-red-300">{move || error.get()}</p>
+            <button
+                on:click=submit
+                class="p-3 w-56 border-2 border-green-600 bg-green-950 hover:border-green-950 rounded-lg"
+            >
+                تسجيل
+            </button>
+        </section>
+    }
+}
+
+#[component]
+pub fn Profile() -> impl IntoView {
+    let user = Resource::once(get_current_user);
+    let username = RwSignal::new(String::new());
+    let current_password = RwSignal::new(String::new());
+    let new_password = RwSignal::new(String::new());
+    let confirm = RwSignal::new(String::new());
+    let message = RwSignal::new(None::<Result<String, String>>);
+    Effect::new(move |_| {
+        if let Some(Ok(Some(user))) = user.get() {
+            username.set(user.username);
+        }
+    });
+
+    let submit = move |_| {
+        let new = new_password.get_untracked();
+        let checked = validate_username(username.get_untracked().trim()).and_then(|_| {
+            if new.is_empty() {
+                return Ok(());
+            }
+            validate_password(&new)?;
+            (new == confirm.get_untracked())
+                .then_some(())
+                .ok_or("كلمتا المرور غير متطابقتين".to_string())
+        });
+        if let Err(err) = checked {
+            message.set(Some(Err(err)));
+            return;
+        }
+        spawn_local(async move {
+            let result = update_profile(
+                username.get_untracked(),
+                current_password.get_untracked(),
+                new,
+            )
+            .await;
+            match result {
+                Ok(_) => {
+                    current_password.set(String::new());
+                    new_password.set(String::new());
+                    confirm.set(String::new());
+                    message.set(Some(Ok("تم حفظ التغييرات".to_string())));
+                }
+                Err(err) => message.set(Some(Err(error_message(err)))),
+            }
+        });
+    };
+
+    view! {
+        <section class="grid justify-items-center gap-5 overflow-auto">
+            <h1 class="text-center m-5 text-3xl">حسابي</h1>
+            <FormInput placeholder="اسم المستخدم" value=username/>
+            <FormInput placeholder="كلمة المرور الحالية" value=current_password password=true/>
+            <FormInput placeholder="كلمة مرور جديدة (اختياري)" value=new_password password=true/>
+            <FormInput placeholder="تاكيد كلمة المرور الجديدة" value=confirm password=true/>
+            {move || {
+                message
+                    .get()
+                    .map(|message| match message {
+                        Ok(message) => view! { <p class="text-green-300">{message}</p> },
+                        Err(message) => view! { <p class="text-red-300">{message}</p> },
+                    })
+            }}
+            <button
+                on:click=submit
+                class="p-3 w-56 border-2 border-green-600 bg-green-950 hover:border-green-950 rounded-lg"
+            >
+                حفظ
+            </button>
+        </section>
+    }
+}
+
+#[component]
+pub fn Users() -> impl IntoView {
+    let users = Resource::once(get_users);
+    let error = RwSignal::new(None::<String>);
+    let done = move |result: Result<(), ServerFnError>| match result {
+        Ok(_) => {
+            error.set(None);
+            users.refetch();
+        }
+        Err(err) => error.set(Some(error_message(err))),
+    };
+
+    view! {
+        <section class="grid justify-items-center gap-5 overflow-auto">
+            <h1 class="text-center m-5 text-3xl">المستخدمون</h1>
+            <p class="text-red-300">{move || error.get()}</p>
+            <Transition>
+                {move || {
+                    users
+                        .get()
+                        .map(|users| match users {
+                            Ok(users) => {
+                                view! {
+                                    <table class="text-xl">
+                                        <For
+                                            each=move || users.clone()
+                                            key=|user| (user.id, user.role.as_str(), user.active)
+                                            let:user
+                                        >
+                                            <UserRow user done/>
+                                        </For>
+                                    </table>
+                                }
+                                    .into_view()
+                            }
+                            Err(err) => view! { <p>{error_message(err)}</p> }.into_view(),
+                        })
+                }}
+            </Transition>
+        </section>
+    }
+}
+
+#[component]
+fn UserRow<F>(user: UserInfo, done: F) -> impl IntoView
+where
+    F: Fn(Result<(), ServerFnError>) + Copy + 'static,
+{
+    let id = user.id;
+    let active = user.active;
+    let on_role = move |ev| {
+        let Ok(role) = Role::from_str(&event_target_value(&ev)) else {
+            return;
+        };
+        spawn_local(async move { done(set_user_role(id, role).await) });
+    };
+    let toggle = move |_| spawn_local(async move { done(set_user_active(id, !active).await) });
+    let role_option = move |role: Role| {
+        view! { <option value=role.as_str() selected=user.role == role>{role.label()}</option> }
+    };
+
+    view! {
+        <tr class=if active { "" } else { "text-gray-400" }>
+            <td class="p-2">{user.username.clone()}</td>
+            <td class="p-2">
+                <select on:change=on_role class="bg-gray-800 rounded-lg p-1">
+                    {role_option(Role::Viewer)}
+                    {role_option(Role::Editor)}
+                    {role_option(Role::BranchAdmin)}
+                    {role_option(Role::SuperAdmin)}
+                </select>
+            </td>
+            <td class="p-2">
+                <button
+                    on:click=toggle
+                    class="px-3 rounded-lg border-2 border-gray-400 bg-gray-950 hover:border-gray-950"
+                >
+                    {if active { "تعطيل" } else { "تفعيل" }}
+                </button>
+            </td>
+        </tr>
+    }
+}
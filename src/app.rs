@@ -1,6 +1,12 @@
 use crate::{
+    accounts::{Profile, Register, Users},
+    auth::Login,
     error_template::{AppError, ErrorTemplate},
-    family_tree::MemberNode,
+    family_tree::{
+        BulkImport, Claims, CsvImport, Duplicates, History, Import, MemberNode, Nasab, Permissions,
+        Proposals, Relation, Trash,
+    },
+    home::Home,
 };
 use leptos::*;
 use leptos_meta::*;
@@ -27,13 +33,23 @@ pub fn App() -> impl IntoView {
                 <Routes>
                     <Route path="" view=Home/>
                     <Route path="exists/:id" view=MemberNode/>
+                    <Route path="nasab/:id" view=Nasab/>
+                    <Route path="relation" view=Relation/>
+                    <Route path="login" view=Login/>
+                    <Route path="register" view=Register/>
+                    <Route path="profile" view=Profile/>
+                    <Route path="admin/users" view=Users/>
+                    <Route path="permissions/:id" view=Permissions/>
+                    <Route path="proposals" view=Proposals/>
+                    <Route path="claims" view=Claims/>
+                    <Route path="history/:id" view=History/>
+                    <Route path="trash" view=Trash/>
+                    <Route path="duplicates" view=Duplicates/>
+                    <Route path="import" view=Import/>
+                    <Route path="bulk" view=BulkImport/>
+                    <Route path="csv" view=CsvImport/>
                 </Routes>
             </main>
         </Router>
     }
 }
-
-#[component]
-fn Home() -> impl IntoView {
-    view! { <h1>"Home"</h1> }
-}
@@ -0,0 +1,314 @@
+use crate::db::users::UserInfo;
+use leptos::*;
+use leptos_router::{use_navigate, A};
+
+#[cfg(feature = "ssr")]
+const SESSION_COOKIE: &str = "ensab_session";
+#[cfg(feature = "ssr")]
+const SESSION_DAYS: i64 = 30;
+// set to serve the session cookie over plain http, anywhere but localhost browsers
+// drop Secure cookies sent without https
+#[cfg(feature = "ssr")]
+const INSECURE_COOKIES: &str = "INSECURE_COOKIES";
+
+#[cfg(feature = "ssr")]
+#[derive(Clone)]
+struct CurrentUser(Option<UserInfo>);
+
+#[cfg(feature = "ssr")]
+fn session_id() -> Option<uuid::Uuid> {
+    let parts = use_context::<http::request::Parts>()?;
+    session_from_headers(&parts.headers)
+}
+
+#[cfg(feature = "ssr")]
+fn session_from_headers(headers: &http::HeaderMap) -> Option<uuid::Uuid> {
+    use std::str::FromStr;
+    headers
+        .get_all(http::header::COOKIE)
+        .iter()
+        .filter_map(|x| x.to_str().ok())
+        .flat_map(|x| x.split(';'))
+        .filter_map(|x| x.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .and_then(|(_, value)| uuid::Uuid::from_str(value).ok())
+}
+
+#[cfg(feature = "ssr")]
+fn set_session_cookie(value: &str, max_age: i64) {
+    let response = expect_context::<leptos_axum::ResponseOptions>();
+    let secure = match std::env::var_os(INSECURE_COOKIES) {
+        Some(_) => "",
+        None => "; Secure",
+    };
+    let cookie = format!(
+        "{SESSION_COOKIE}={value}; Path=/; HttpOnly; SameSite=Lax; Max-Age={max_age}{secure}"
+    );
+    if let Ok(cookie) = http::HeaderValue::from_str(&cookie) {
+        response.append_header(http::header::SET_COOKIE, cookie);
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub async fn current_user() -> Result<Option<UserInfo>, ServerFnError> {
+    use crate::db::{sessions, Pool, Postgres};
+    if let Some(CurrentUser(user)) = use_context::<CurrentUser>() {
+        return Ok(user);
+    }
+    let user = match session_id() {
+        Some(id) => {
+            let pool = expect_context::<Pool<Postgres>>();
+            let Ok(user) = sessions::user(&pool, id).await else {
+                return Err(ServerFnError::ServerError(
+                    "error reading session".to_string(),
+                ));
+            };
+            user.map(|x| x.info())
+        }
+        None => None,
+    };
+    provide_context(CurrentUser(user.clone()));
+    Ok(user)
+}
+
+// the signed in user of a request served outside of the server functions
+#[cfg(feature = "ssr")]
+pub async fn request_user(
+    pool: &crate::db::Pool<crate::db::Postgres>,
+    headers: &http::HeaderMap,
+) -> anyhow::Result<Option<UserInfo>> {
+    use crate::db::sessions;
+    let Some(id) = session_from_headers(headers) else {
+        return Ok(None);
+    };
+    Ok(sessions::user(pool, id).await?.map(|x| x.info()))
+}
+
+#[cfg(feature = "ssr")]
+pub async fn require_user() -> Result<UserInfo, ServerFnError> {
+    current_user()
+        .await?
+        .ok_or_else(|| ServerFnError::ServerError("login required".to_string()))
+}
+
+// the subset of `ids` the user may edit
+#[cfg(feature = "ssr")]
+pub async fn editable(
+    user: &UserInfo,
+    ids: Vec<uuid::Uuid>,
+) -> Result<std::collections::HashSet<uuid::Uuid>, ServerFnError> {
+    use crate::db::{Pool, Postgres};
+    let pool = expect_context::<Pool<Postgres>>();
+    let Ok(editable) = editable_in(&pool, user, ids).await else {
+        return Err(ServerFnError::ServerError(
+            "error reading permissions".to_string(),
+        ));
+    };
+    Ok(editable)
+}
+
+#[cfg(feature = "ssr")]
+pub async fn editable_in(
+    pool: &crate::db::Pool<crate::db::Postgres>,
+    user: &UserInfo,
+    ids: Vec<uuid::Uuid>,
+) -> anyhow::Result<std::collections::HashSet<uuid::Uuid>> {
+    use crate::db::{users, users::Role};
+    match user.role {
+        Role::SuperAdmin => Ok(ids.into_iter().collect()),
+        Role::Viewer => Ok(Default::default()),
+        Role::Editor | Role::BranchAdmin => Ok(users::editable(pool, user.id, &ids)
+            .await?
+            .into_iter()
+            .collect()),
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub async fn require_branch_admin(root: uuid::Uuid) -> Result<UserInfo, ServerFnError> {
+    use crate::db::users::Role;
+    let user = require_user().await?;
+    let allowed = match user.role {
+        Role::SuperAdmin => true,
+        Role::BranchAdmin => editable(&user, vec![root]).await?.contains(&root),
+        Role::Viewer | Role::Editor => false,
+    };
+    if !allowed {
+        return Err(ServerFnError::ServerError("permission denied".to_string()));
+    }
+    Ok(user)
+}
+
+#[server(encoding = "Cbor")]
+async fn login(username: String, password: String) -> Result<UserInfo, ServerFnError> {
+    use crate::db::{users, Pool, Postgres};
+    let pool = expect_context::<Pool<Postgres>>();
+    let Ok(user) = users::authenticate(&pool, username.trim(), &password).await else {
+        return Err(ServerFnError::ServerError("error reading user".to_string()));
+    };
+    let Some(user) = user else {
+        return Err(ServerFnError::ServerError(
+            "wrong username or password".to_string(),
+        ));
+    };
+    start_session(&user.info()).await?;
+    Ok(user.info())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn start_session(user: &UserInfo) -> Result<(), ServerFnError> {
+    use crate::db::{sessions, Pool, Postgres};
+    let pool = expect_context::<Pool<Postgres>>();
+    let _ = sessions::delete_expired(&pool).await;
+    let Ok(session) = sessions::create(&pool, user.id, SESSION_DAYS).await else {
+        return Err(ServerFnError::ServerError(
+            "error creating session".to_string(),
+        ));
+    };
+    set_session_cookie(&session.to_string(), SESSION_DAYS * 24 * 60 * 60);
+    provide_context(CurrentUser(Some(user.clone())));
+    Ok(())
+}
+
+#[server(encoding = "Cbor")]
+async fn logout() -> Result<(), ServerFnError> {
+    use crate::db::{sessions, Pool, Postgres};
+    let pool = expect_context::<Pool<Postgres>>();
+    if let Some(id) = session_id() {
+        let Ok(_) = sessions::delete(&pool, id).await else {
+            return Err(ServerFnError::ServerError(
+                "error deleting session".to_string(),
+            ));
+        };
+    }
+    set_session_cookie("", 0);
+    provide_context(CurrentUser(None));
+    Ok(())
+}
+
+#[server(encoding = "Cbor")]
+pub async fn get_current_user() -> Result<Option<UserInfo>, ServerFnError> {
+    current_user().await
+}
+
+#[component]
+pub fn Login() -> impl IntoView {
+    let username_ref = create_node_ref::<html::Input>();
+    let password_ref = create_node_ref::<html::Input>();
+    let error = RwSignal::new(None::<String>);
+    let navigate = use_navigate();
+
+    let submit = move |_| {
+        let username = username_ref.get().unwrap().value();
+        let password = password_ref.get().unwrap().value();
+        let navigate = navigate.clone();
+        spawn_local(async move {
+            match login(username, password).await {
+                Ok(_) => navigate("/", Default::default()),
+                Err(_) => error.set(Some("اسم المستخدم او كلمة المرور غير صحيحة".to_string())),
+            }
+        });
+    };
+
+    view! {
+        <section class="grid justify-items-center gap-5 overflow-auto">
+            <h1 class="text-center m-5 text-3xl">تسجيل الدخول</h1>
+            <input
+                class="placeholder:text-center placeholder-gray-400 bg-gray-800 border-gray-500 hover:border-gray-800 text-center border-2 p-2 text-2xl rounded-lg w-96"
+                placeholder="اسم المستخدم"
+                node_ref=username_ref
+            />
+            <input
+                type="password"
+                class="placeholder:text-center placeholder-gray-400 bg-gray-800 border-gray-500 hover:border-gray-800 text-center border-2 p-2 text-2xl rounded-lg w-96"
+                placeholder="كلمة المرور"
+                node_ref=password_ref
+            />
+            <p class="text-red-300">{move || error.get()}</p>
+            <button
+                on:click=submit
+                class="p-3 w-56 border-2 border-green-600 bg-green-950 hover:border-green-950 rounded-lg"
+            >
+                دخول
+            </button>
+        </section>
+    }
+}
+
+#[component]
+pub fn UserMenu() -> impl IntoView {
+    let user = Resource::once(get_current_user);
+    let sign_out = move |_| {
+        spawn_local(async move {
+            if logout().await.is_ok() {
+                user.refetch();
+            }
+        });
+    };
+
+    view! {
+        <Transition>
+            {move || {
+                user.get()
+                    .and_then(Result::ok)
+                    .map(|user| match user {
+                        Some(user) => {
+                            view! {
+                                <div class="flex flex-row gap-3 items-center">
+                                    <A href="/profile" class="hover:underline">
+                                        {user.username}
+                                    </A>
+                                    {(user.role == crate::db::users::Role::SuperAdmin)
+                                        .then(|| {
+                                            view! {
+                                                <A href="/admin/users" class="hover:underline">
+                                                    المستخدمون
+                                                </A>
+                                            }
+                                        })}
+                                    <A href="/proposals" class="hover:underline">
+                                        الاقتراحات
+                                    </A>
+                                    <A href="/claims" class="hover:underline">
+                                        طلبات الربط
+                                    </A>
+                                    <A href="/trash" class="hover:underline">
+                                        المحذوفات
+                                    </A>
+                                    <A href="/duplicates" class="hover:underline">
+                                        المكررون
+                                    </A>
+                                    <A href="/import" class="hover:underline">
+                                        استيراد
+                                    </A>
+                                    <A href="/bulk" class="hover:underline">
+                                        اضافة جماعية
+                                    </A>
+                                    <A href="/csv" class="hover:underline">
+                                        استيراد جدول
+                                    </A>
+                                    <button on:click=sign_out class="px-3 rounded-lg border-2 border-red-600 bg-red-950 hover:border-red-950">
+                                        تسجيل الخروج
+                                    </button>
+                                </div>
+                            }
+                                .into_view()
+                        }
+                        None => {
+                            view! {
+                                <div class="flex flex-row gap-3 items-center">
+                                    <A href="/login" class="hover:underline">
+                                        تسجيل الدخول
+                                    </A>
+                                    <A href="/register" class="hover:underline">
+                                        انشاء حساب
+                                    </A>
+                                </div>
+                            }
+                                .into_view()
+                        }
+                    })
+            }}
+        </Transition>
+    }
+}
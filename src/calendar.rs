@@ -0,0 +1,251 @@
+use std::str::FromStr;
+
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+// julian day number of 1 muharram 1 in the tabular (civil) hijri calendar
+const HIJRI_EPOCH: i32 = 1948440;
+// julian day number of the day before 1 january 1 in the proleptic gregorian calendar
+const GREGORIAN_EPOCH: i32 = 1721425;
+
+const GREGORIAN_MONTHS: [&str; 12] = [
+    "يناير", "فبراير", "مارس", "ابريل", "مايو", "يونيو", "يوليو", "اغسطس", "سبتمبر", "اكتوبر",
+    "نوفمبر", "ديسمبر",
+];
+
+const HIJRI_MONTHS: [&str; 12] = [
+    "محرم",
+    "صفر",
+    "ربيع الاول",
+    "ربيع الاخر",
+    "جمادى الاولى",
+    "جمادى الاخرة",
+    "رجب",
+    "شعبان",
+    "رمضان",
+    "شوال",
+    "ذو القعدة",
+    "ذو الحجة",
+];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Calendar {
+    #[default]
+    Gregorian,
+    Hijri,
+}
+
+impl Calendar {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Calendar::Gregorian => "gregorian",
+            Calendar::Hijri => "hijri",
+        }
+    }
+
+    pub fn months(&self) -> [&'static str; 12] {
+        match self {
+            Calendar::Gregorian => GREGORIAN_MONTHS,
+            Calendar::Hijri => HIJRI_MONTHS,
+        }
+    }
+
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            Calendar::Gregorian => "م",
+            Calendar::Hijri => "هـ",
+        }
+    }
+
+    pub fn to_date(&self, year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+        match self {
+            Calendar::Gregorian => NaiveDate::from_ymd_opt(year, month, day),
+            Calendar::Hijri => {
+                if !(1..=12).contains(&month) || day < 1 || day > hijri_month_length(year, month) {
+                    return None;
+                }
+                let days = hijri_to_jdn(year, month, day) - GREGORIAN_EPOCH;
+                NaiveDate::from_num_days_from_ce_opt(days)
+            }
+        }
+    }
+
+    pub fn parts(&self, date: NaiveDate) -> (i32, u32, u32) {
+        match self {
+            Calendar::Gregorian => (date.year(), date.month(), date.day()),
+            Calendar::Hijri => jdn_to_hijri(date.num_days_from_ce() + GREGORIAN_EPOCH),
+        }
+    }
+}
+
+impl FromStr for Calendar {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gregorian" => Ok(Calendar::Gregorian),
+            "hijri" => Ok(Calendar::Hijri),
+            _ => Err(anyhow::anyhow!("unknown calendar {s}")),
+        }
+    }
+}
+
+fn hijri_is_leap(year: i32) -> bool {
+    (14 + 11 * year).rem_euclid(30) < 11
+}
+
+fn hijri_month_length(year: i32, month: u32) -> u32 {
+    if month % 2 == 1 || (month == 12 && hijri_is_leap(year)) {
+        30
+    } else {
+        29
+    }
+}
+
+fn hijri_to_jdn(year: i32, month: u32, day: u32) -> i32 {
+    let month = month as i32;
+    day as i32 + (59 * (month - 1) + 1) / 2 + 354 * (year - 1) + (3 + 11 * year).div_euclid(30)
+        + HIJRI_EPOCH
+        - 1
+}
+
+fn jdn_to_hijri(jdn: i32) -> (i32, u32, u32) {
+    let year = (30 * (jdn - HIJRI_EPOCH) + 10646).div_euclid(10631);
+    let month = (1..12)
+        .take_while(|month| jdn >= hijri_to_jdn(year, month + 1, 1))
+        .count() as u32
+        + 1;
+    let day = jdn - hijri_to_jdn(year, month, 1) + 1;
+    (year, month, day as u32)
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum DatePrecision {
+    #[default]
+    Exact,
+    Year,
+    Approximate,
+    Before,
+    After,
+}
+
+impl DatePrecision {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DatePrecision::Exact => "exact",
+            DatePrecision::Year => "year",
+            DatePrecision::Approximate => "approximate",
+            DatePrecision::Before => "before",
+            DatePrecision::After => "after",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DatePrecision::Exact => "تاريخ دقيق",
+            DatePrecision::Year => "السنة فقط",
+            DatePrecision::Approximate => "تقريبا",
+            DatePrecision::Before => "قبل",
+            DatePrecision::After => "بعد",
+        }
+    }
+}
+
+impl FromStr for DatePrecision {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "exact" => Ok(DatePrecision::Exact),
+            "year" => Ok(DatePrecision::Year),
+            "approximate" => Ok(DatePrecision::Approximate),
+            "before" => Ok(DatePrecision::Before),
+            "after" => Ok(DatePrecision::After),
+            _ => Err(anyhow::anyhow!("unknown date precision {s}")),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MemberDate {
+    pub date: NaiveDate,
+    pub precision: DatePrecision,
+}
+
+impl MemberDate {
+    pub fn from_columns(date: Option<NaiveDate>, precision: Option<String>) -> Option<Self> {
+        Some(Self {
+            date: date?,
+            precision: precision
+                .and_then(|x| x.parse().ok())
+                .unwrap_or_default(),
+        })
+    }
+
+    pub fn display(&self, calendar: Calendar) -> String {
+        let (year, month, day) = calendar.parts(self.date);
+        let suffix = calendar.suffix();
+        match self.precision {
+            DatePrecision::Exact => {
+                let month = calendar.months()[month as usize - 1];
+                format!("{day} {month} {year} {suffix}")
+            }
+            DatePrecision::Year => format!("{year} {suffix}"),
+            DatePrecision::Approximate => format!("حوالي {year} {suffix}"),
+            DatePrecision::Before => format!("قبل {year} {suffix}"),
+            DatePrecision::After => format!("بعد {year} {suffix}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Ymd = (i32, u32, u32);
+
+    // hijri dates and the gregorian days they fall on
+    const PAIRS: [(Ymd, Ymd); 4] = [
+        ((1, 1, 1), (622, 7, 19)),
+        ((1400, 1, 1), (1979, 11, 21)),
+        ((1444, 9, 1), (2023, 3, 23)),
+        ((1446, 1, 1), (2024, 7, 8)),
+    ];
+
+    #[test]
+    fn hijri_to_gregorian() {
+        for ((year, month, day), (y, m, d)) in PAIRS {
+            assert_eq!(
+                Calendar::Hijri.to_date(year, month, day),
+                NaiveDate::from_ymd_opt(y, m, d)
+            );
+        }
+    }
+
+    #[test]
+    fn gregorian_to_hijri() {
+        for (hijri, (y, m, d)) in PAIRS {
+            let date = NaiveDate::from_ymd_opt(y, m, d).unwrap();
+            assert_eq!(Calendar::Hijri.parts(date), hijri);
+        }
+    }
+
+    #[test]
+    fn leap_year_boundary() {
+        assert!(hijri_is_leap(1445));
+        assert!(!hijri_is_leap(1444));
+        let last = Calendar::Hijri.to_date(1445, 12, 30).unwrap();
+        assert_eq!(last, NaiveDate::from_ymd_opt(2024, 7, 7).unwrap());
+        assert_eq!(Calendar::Hijri.parts(last), (1445, 12, 30));
+        assert_eq!(
+            Calendar::Hijri.parts(last.succ_opt().unwrap()),
+            (1446, 1, 1)
+        );
+        assert_eq!(Calendar::Hijri.to_date(1444, 12, 30), None);
+        let last = Calendar::Hijri.to_date(1444, 12, 29).unwrap();
+        assert_eq!(
+            Calendar::Hijri.parts(last.succ_opt().unwrap()),
+            (1445, 1, 1)
+        );
+    }
+}
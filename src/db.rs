@@ -1,7 +1,12 @@
 #[cfg(feature = "ssr")]
-pub use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
+pub use sqlx::{postgres::PgPoolOptions, Pool, Postgres, Transaction};
 
+pub mod claims;
+pub mod history;
+pub mod marriage;
 pub mod member;
+pub mod proposals;
+pub mod sessions;
 pub mod users;
 
 #[cfg(feature = "ssr")]
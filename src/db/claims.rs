@@ -0,0 +1,125 @@
+#[cfg(feature = "ssr")]
+use sqlx::{query, Pool, Postgres, Transaction};
+#[cfg(feature = "ssr")]
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+pub struct Claim {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub username: String,
+    pub member_id: Uuid,
+}
+
+#[cfg(feature = "ssr")]
+pub async fn create(pool: &Pool<Postgres>, user_id: Uuid, member_id: Uuid) -> anyhow::Result<()> {
+    use chrono::Utc;
+    query!(
+        r#"
+            INSERT INTO member_claims (id,user_id,member_id,created_at)
+            VALUES($1,$2,$3,$4)
+            ON CONFLICT DO NOTHING;
+            "#,
+        Uuid::new_v4(),
+        user_id,
+        member_id,
+        Utc::now().naive_utc()
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn pending(pool: &Pool<Postgres>) -> anyhow::Result<Vec<Claim>> {
+    let claims = query!(
+        r#"
+            SELECT c.id,c.user_id,u.username,c.member_id
+            FROM member_claims c JOIN users u ON u.id = c.user_id
+            WHERE c.status = 'pending' ORDER BY c.created_at
+            "#
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| Claim {
+        id: x.id,
+        user_id: x.user_id,
+        username: x.username,
+        member_id: x.member_id,
+    })
+    .collect();
+    Ok(claims)
+}
+
+#[cfg(feature = "ssr")]
+pub async fn read(pool: &Pool<Postgres>, id: Uuid) -> anyhow::Result<Claim> {
+    let claim = query!(
+        r#"
+            SELECT c.id,c.user_id,u.username,c.member_id
+            FROM member_claims c JOIN users u ON u.id = c.user_id
+            WHERE c.id = $1 AND c.status = 'pending'
+            "#,
+        id
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(Claim {
+        id: claim.id,
+        user_id: claim.user_id,
+        username: claim.username,
+        member_id: claim.member_id,
+    })
+}
+
+#[cfg(feature = "ssr")]
+pub async fn review(
+    transaction: &mut Transaction<'_, Postgres>,
+    claim: &Claim,
+    approve: bool,
+    reviewer_id: Uuid,
+) -> anyhow::Result<()> {
+    use chrono::Utc;
+    let status = if approve { "approved" } else { "rejected" };
+    let result = query!(
+        r#"
+            update member_claims set status = $2,reviewer_id = $3,reviewed_at = $4
+            where id = $1 and status = 'pending'
+            "#,
+        claim.id,
+        status,
+        reviewer_id,
+        Utc::now().naive_utc()
+    )
+    .execute(&mut **transaction)
+    .await?;
+    if result.rows_affected() == 0 {
+        anyhow::bail!("claim {} was already reviewed", claim.id);
+    }
+    if approve {
+        let deleted = query!(
+            r#"SELECT deleted_at IS NOT NULL as "deleted!" FROM member WHERE id = $1 FOR SHARE"#,
+            claim.member_id
+        )
+        .fetch_one(&mut **transaction)
+        .await?
+        .deleted;
+        if deleted {
+            anyhow::bail!("member {} is in the trash", claim.member_id);
+        }
+        query!(
+            "update users set member_id = NULL where member_id = $1",
+            claim.member_id
+        )
+        .execute(&mut **transaction)
+        .await?;
+        query!(
+            "update users set member_id = $2 where id = $1",
+            claim.user_id,
+            claim.member_id
+        )
+        .execute(&mut **transaction)
+        .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,201 @@
+use std::str::FromStr;
+
+use super::member::SonlessRawMember;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ssr")]
+use sqlx::{query, Pool, Postgres, Transaction};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HistoryAction {
+    Create,
+    Update,
+    Delete,
+    Restore,
+    Purge,
+    Merge,
+}
+
+impl HistoryAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HistoryAction::Create => "create",
+            HistoryAction::Update => "update",
+            HistoryAction::Delete => "delete",
+            HistoryAction::Restore => "restore",
+            HistoryAction::Purge => "purge",
+            HistoryAction::Merge => "merge",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            HistoryAction::Create => "اضافة",
+            HistoryAction::Update => "تعديل",
+            HistoryAction::Delete => "حذف",
+            HistoryAction::Restore => "استعادة من المحذوفات",
+            HistoryAction::Purge => "حذف نهائي",
+            HistoryAction::Merge => "دمج",
+        }
+    }
+}
+
+impl FromStr for HistoryAction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "create" => Ok(HistoryAction::Create),
+            "update" => Ok(HistoryAction::Update),
+            "delete" => Ok(HistoryAction::Delete),
+            "restore" => Ok(HistoryAction::Restore),
+            "purge" => Ok(HistoryAction::Purge),
+            "merge" => Ok(HistoryAction::Merge),
+            _ => Err(anyhow::anyhow!("unknown history action {s}")),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Snapshot {
+    pub parent_id: Option<Uuid>,
+    pub member: SonlessRawMember,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HistoryEntry {
+    pub id: Uuid,
+    pub batch_id: Uuid,
+    pub member_id: Uuid,
+    pub action: HistoryAction,
+    pub author: Option<String>,
+    pub changed_at: NaiveDateTime,
+    pub before: Option<Snapshot>,
+    pub after: Option<Snapshot>,
+}
+
+// groups the history rows written by one commit
+#[cfg(feature = "ssr")]
+#[derive(Clone, Copy, Debug)]
+pub struct Batch {
+    pub id: Uuid,
+    pub author_id: Uuid,
+}
+
+#[cfg(feature = "ssr")]
+impl Batch {
+    pub fn new(author_id: Uuid) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            author_id,
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub async fn record(
+    transaction: &mut Transaction<'_, Postgres>,
+    batch: &Batch,
+    action: HistoryAction,
+    before: Option<&Snapshot>,
+    after: Option<&Snapshot>,
+) -> anyhow::Result<()> {
+    use chrono::Utc;
+    let Some(current) = after.or(before) else {
+        anyhow::bail!("history entry without a snapshot");
+    };
+    query!(
+        r#"
+            INSERT INTO member_history (id,batch_id,member_id,parent_id,action,author_id,changed_at,before,after)
+            VALUES($1,$2,$3,$4,$5,$6,$7,$8,$9);
+            "#,
+        Uuid::new_v4(),
+        batch.id,
+        current.member.id,
+        current.parent_id,
+        action.as_str(),
+        batch.author_id,
+        Utc::now().naive_utc(),
+        before.map(serde_json::to_value).transpose()?,
+        after.map(serde_json::to_value).transpose()?
+    )
+    .execute(&mut **transaction)
+    .await?;
+    Ok(())
+}
+
+// the changes of a member and the additions and removals of its direct sons
+#[cfg(feature = "ssr")]
+pub async fn for_member(pool: &Pool<Postgres>, id: Uuid) -> anyhow::Result<Vec<HistoryEntry>> {
+    let records = query!(
+        r#"
+            SELECT h.id,h.batch_id,h.member_id,h.action,u.username as "username?",
+                h.changed_at,h.before,h.after
+            FROM member_history h LEFT JOIN users u ON u.id = h.author_id
+            WHERE h.member_id = $1 OR (h.parent_id = $1 AND h.action <> 'update')
+            ORDER BY h.changed_at DESC
+            "#,
+        id
+    )
+    .fetch_all(pool)
+    .await?;
+    let mut entries = Vec::new();
+    for record in records {
+        entries.push(HistoryEntry {
+            id: record.id,
+            batch_id: record.batch_id,
+            member_id: record.member_id,
+            action: record.action.parse()?,
+            author: record.username,
+            changed_at: record.changed_at,
+            before: record.before.map(serde_json::from_value).transpose()?,
+            after: record.after.map(serde_json::from_value).transpose()?,
+        });
+    }
+    Ok(entries)
+}
+
+#[cfg(feature = "ssr")]
+pub async fn read(pool: &Pool<Postgres>, id: Uuid) -> anyhow::Result<HistoryEntry> {
+    let record = query!(
+        r#"
+            SELECT h.id,h.batch_id,h.member_id,h.action,u.username as "username?",
+                h.changed_at,h.before,h.after
+            FROM member_history h LEFT JOIN users u ON u.id = h.author_id
+            WHERE h.id = $1
+            "#,
+        id
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(HistoryEntry {
+        id: record.id,
+        batch_id: record.batch_id,
+        member_id: record.member_id,
+        action: record.action.parse()?,
+        author: record.username,
+        changed_at: record.changed_at,
+        before: record.before.map(serde_json::from_value).transpose()?,
+        after: record.after.map(serde_json::from_value).transpose()?,
+    })
+}
+
+// every member removed by the given batch, as they were before the deletion
+#[cfg(feature = "ssr")]
+pub async fn deleted(pool: &Pool<Postgres>, batch_id: Uuid) -> anyhow::Result<Vec<Snapshot>> {
+    let records = query!(
+        r#"
+            SELECT before as "before!" FROM member_history
+            WHERE batch_id = $1 AND action = 'delete' AND before IS NOT NULL
+            "#,
+        batch_id
+    )
+    .fetch_all(pool)
+    .await?;
+    let mut snapshots = Vec::new();
+    for record in records {
+        snapshots.push(serde_json::from_value(record.before)?);
+    }
+    Ok(snapshots)
+}
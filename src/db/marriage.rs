@@ -0,0 +1,191 @@
+#[cfg(feature = "ssr")]
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ssr")]
+use sqlx::{query, Pool, Postgres, Transaction};
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+use super::member;
+use super::member::SonlessRawMember;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub enum MarriageStatus {
+    #[default]
+    Married,
+    Divorced,
+    Widowed,
+}
+
+impl MarriageStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MarriageStatus::Married => "married",
+            MarriageStatus::Divorced => "divorced",
+            MarriageStatus::Widowed => "widowed",
+        }
+    }
+}
+
+impl FromStr for MarriageStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "married" => Ok(MarriageStatus::Married),
+            "divorced" => Ok(MarriageStatus::Divorced),
+            "widowed" => Ok(MarriageStatus::Widowed),
+            _ => Err(anyhow::anyhow!("unknown marriage status {s}")),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Marriage {
+    pub id: Uuid,
+    pub husband: SonlessRawMember,
+    pub wife: SonlessRawMember,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub status: MarriageStatus,
+}
+
+impl Marriage {
+    pub fn new(husband: SonlessRawMember, wife: SonlessRawMember) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            husband,
+            wife,
+            ..Default::default()
+        }
+    }
+
+    pub fn spouse_of(&self, id: Uuid) -> Option<&SonlessRawMember> {
+        if self.husband.id == id {
+            Some(&self.wife)
+        } else if self.wife.id == id {
+            Some(&self.husband)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub async fn create(
+    transaction: &mut Transaction<'_, Postgres>,
+    Marriage {
+        id,
+        husband,
+        wife,
+        start_date,
+        end_date,
+        status,
+    }: Marriage,
+) -> anyhow::Result<()> {
+    query!(
+        r#"
+            INSERT INTO marriage (id,husband_id,wife_id,start_date,end_date,status)
+            VALUES($1,$2,$3,$4,$5,$6);
+            "#,
+        id,
+        husband.id,
+        wife.id,
+        start_date,
+        end_date,
+        status.as_str()
+    )
+    .execute(&mut **transaction)
+    .await?;
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn update(
+    transaction: &mut Transaction<'_, Postgres>,
+    marriages: Vec<Marriage>,
+) -> anyhow::Result<()> {
+    for marriage in marriages {
+        query!(
+            r#"
+                update marriage set start_date = $2,end_date = $3,status = $4 where id = $1
+                "#,
+            marriage.id,
+            marriage.start_date,
+            marriage.end_date,
+            marriage.status.as_str()
+        )
+        .execute(&mut **transaction)
+        .await?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn delete(transaction: &mut Transaction<'_, Postgres>, id: Uuid) -> anyhow::Result<()> {
+    query!("delete from marriage where id = $1", id)
+        .execute(&mut **transaction)
+        .await?;
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn read(pool: &Pool<Postgres>, members: &[Uuid]) -> anyhow::Result<Vec<Marriage>> {
+    let records = query!(
+        r#"
+            SELECT m.id,m.husband_id,m.wife_id,m.start_date,m.end_date,m.status
+            FROM marriage m
+            JOIN member h ON h.id = m.husband_id AND h.deleted_at IS NULL
+            JOIN member w ON w.id = m.wife_id AND w.deleted_at IS NULL
+            WHERE m.husband_id = ANY($1) OR m.wife_id = ANY($1)
+            ORDER BY m.start_date NULLS LAST
+            "#,
+        members
+    )
+    .fetch_all(pool)
+    .await?;
+    let ids = records
+        .iter()
+        .flat_map(|x| [x.husband_id, x.wife_id])
+        .collect::<Vec<_>>();
+    let spouses = member::read_sonless(pool, &ids)
+        .await?
+        .into_iter()
+        .map(|x| (x.id, x))
+        .collect::<HashMap<_, _>>();
+    let mut marriages = Vec::new();
+    for record in records {
+        let (Some(husband), Some(wife)) = (
+            spouses.get(&record.husband_id),
+            spouses.get(&record.wife_id),
+        ) else {
+            anyhow::bail!("marriage {} has a missing spouse", record.id);
+        };
+        marriages.push(Marriage {
+            id: record.id,
+            husband: husband.clone(),
+            wife: wife.clone(),
+            start_date: record.start_date,
+            end_date: record.end_date,
+            status: record.status.parse()?,
+        });
+    }
+    Ok(marriages)
+}
+
+#[cfg(feature = "ssr")]
+pub async fn spouses(pool: &Pool<Postgres>, ids: &[Uuid]) -> anyhow::Result<Vec<(Uuid, Uuid)>> {
+    let spouses = query!(
+        "SELECT husband_id,wife_id FROM marriage WHERE id = ANY($1)",
+        ids
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| (x.husband_id, x.wife_id))
+    .collect();
+    Ok(spouses)
+}
@@ -1,14 +1,27 @@
+#[cfg(feature = "ssr")]
+use super::history::{self, Batch, HistoryAction, Snapshot};
+use crate::calendar::MemberDate;
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "ssr")]
 use sqlx::{query, Pool, Postgres, Transaction};
 use uuid::Uuid;
 
+// the length of the member name column, VARCHAR(150) since 0006_name_parts
+pub const NAME_LENGTH: usize = 150;
+
 #[derive(Serialize, Deserialize, Clone, Default, PartialEq, Eq, Debug)]
 pub struct RawMember {
     pub id: Uuid,
     pub name: String,
     pub is_male: bool,
+    pub mother_id: Option<Uuid>,
+    pub birth: Option<MemberDate>,
+    pub death: Option<MemberDate>,
+    pub names: NameParts,
+    #[serde(default)]
+    pub version: i32,
     pub sons: Vec<RawMember>,
+    pub unloaded_sons: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
@@ -16,6 +29,59 @@ pub struct SonlessRawMember {
     pub id: Uuid,
     pub name: String,
     pub is_male: bool,
+    pub mother_id: Option<Uuid>,
+    pub birth: Option<MemberDate>,
+    pub death: Option<MemberDate>,
+    pub names: NameParts,
+    #[serde(default)]
+    pub version: i32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Eq, Debug)]
+pub struct NameParts {
+    pub kunya: Option<String>,
+    pub laqab: Option<String>,
+    pub nisba: Option<String>,
+    pub alternate: Vec<String>,
+}
+
+impl SonlessRawMember {
+    pub fn with_sons(self, sons: Vec<RawMember>, unloaded_sons: bool) -> RawMember {
+        RawMember {
+            id: self.id,
+            name: self.name,
+            is_male: self.is_male,
+            mother_id: self.mother_id,
+            birth: self.birth,
+            death: self.death,
+            names: self.names,
+            version: self.version,
+            sons,
+            unloaded_sons,
+        }
+    }
+}
+
+// builds a SonlessRawMember out of any record selecting the member columns
+#[cfg(feature = "ssr")]
+macro_rules! sonless_raw_member {
+    ($record:ident) => {
+        SonlessRawMember {
+            id: $record.id,
+            name: $record.name,
+            is_male: $record.is_male,
+            mother_id: $record.mother_id,
+            birth: MemberDate::from_columns($record.birth_date, $record.birth_precision),
+            death: MemberDate::from_columns($record.death_date, $record.death_precision),
+            names: NameParts {
+                kunya: $record.kunya,
+                laqab: $record.laqab,
+                nisba: $record.nisba,
+                alternate: $record.alt_names,
+            },
+            version: $record.version,
+        }
+    };
 }
 
 #[cfg(feature = "ssr")]
@@ -25,28 +91,79 @@ pub async fn create(
         id,
         name,
         is_male,
+        mother_id,
+        birth,
+        death,
+        names,
+        version,
         sons,
+        ..
     }: RawMember,
     parent_id: Option<Uuid>,
+    batch: &Batch,
 ) -> anyhow::Result<()> {
     use chrono::{NaiveDateTime, Utc};
+    let parent_deleted = query!(
+        r#"SELECT EXISTS(SELECT 1 FROM member WHERE id = $1 AND deleted_at IS NOT NULL) as "deleted!""#,
+        parent_id
+    )
+    .fetch_one(&mut **transaction)
+    .await?
+    .deleted;
+    if parent_deleted {
+        anyhow::bail!("member {id} can not be added to a deleted parent");
+    }
     let now = Utc::now();
     let now = NaiveDateTime::new(now.date_naive(), now.time());
+    let snapshot = Snapshot {
+        parent_id,
+        member: SonlessRawMember {
+            id,
+            name,
+            is_male,
+            mother_id,
+            birth,
+            death,
+            names,
+            version: version.max(1),
+        },
+    };
+    let member = &snapshot.member;
     query!(
         r#"
-            INSERT INTO "member" (id,"name",is_male,insert_date,parent_id)
-            VALUES($1,$2,$3,$4,$5);
+            INSERT INTO "member" (id,"name",is_male,insert_date,parent_id,mother_id,
+                birth_date,birth_precision,death_date,death_precision,
+                kunya,laqab,nisba,alt_names,version)
+            VALUES($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15);
             "#,
-        id,
-        name,
-        is_male,
+        member.id,
+        member.name,
+        member.is_male,
         now,
-        parent_id
+        parent_id,
+        member.mother_id,
+        member.birth.map(|x| x.date),
+        member.birth.map(|x| x.precision.as_str()),
+        member.death.map(|x| x.date),
+        member.death.map(|x| x.precision.as_str()),
+        member.names.kunya,
+        member.names.laqab,
+        member.names.nisba,
+        &member.names.alternate,
+        member.version
     )
     .execute(&mut **transaction)
     .await?;
+    history::record(
+        transaction,
+        batch,
+        HistoryAction::Create,
+        None,
+        Some(&snapshot),
+    )
+    .await?;
     for son in sons {
-        Box::pin(create(transaction, son, Some(id))).await?;
+        Box::pin(create(transaction, son, Some(id), batch)).await?;
     }
 
     Ok(())
@@ -56,55 +173,1120 @@ pub async fn create(
 pub async fn update(
     transaction: &mut Transaction<'_, Postgres>,
     members: Vec<SonlessRawMember>,
+    batch: &Batch,
 ) -> anyhow::Result<()> {
     for member in members {
-        query!(
+        let Some(before) = snapshots(transaction, member.id, false).await?.pop() else {
+            anyhow::bail!("member {} not found", member.id);
+        };
+        let result = query!(
             r#"
-                update member set name = $2,is_male = $3 where id = $1
+                update member set name = $2,is_male = $3,mother_id = $4,
+                    birth_date = $5,birth_precision = $6,death_date = $7,death_precision = $8,
+                    kunya = $9,laqab = $10,nisba = $11,alt_names = $12,version = version + 1
+                where id = $1 AND version = $13 AND deleted_at IS NULL
                 "#,
             member.id,
             member.name,
-            member.is_male
+            member.is_male,
+            member.mother_id,
+            member.birth.map(|x| x.date),
+            member.birth.map(|x| x.precision.as_str()),
+            member.death.map(|x| x.date),
+            member.death.map(|x| x.precision.as_str()),
+            member.names.kunya,
+            member.names.laqab,
+            member.names.nisba,
+            &member.names.alternate,
+            member.version
         )
         .execute(&mut **transaction)
         .await?;
+        if result.rows_affected() == 0 {
+            anyhow::bail!("member {} was changed by someone else", member.id);
+        }
+        let after = Snapshot {
+            parent_id: before.parent_id,
+            member: SonlessRawMember {
+                version: member.version + 1,
+                ..member
+            },
+        };
+        history::record(
+            transaction,
+            batch,
+            HistoryAction::Update,
+            Some(&before),
+            Some(&after),
+        )
+        .await?;
     }
     Ok(())
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MovedMember {
+    pub id: Uuid,
+    pub parent_id: Uuid,
+    pub version: i32,
+}
+
+// attaches the member and its descendants to another parent
+#[cfg(feature = "ssr")]
+pub async fn reparent(
+    transaction: &mut Transaction<'_, Postgres>,
+    moved: &MovedMember,
+    batch: &Batch,
+) -> anyhow::Result<()> {
+    let Some(before) = snapshots(transaction, moved.id, false).await?.pop() else {
+        anyhow::bail!("member {} not found", moved.id);
+    };
+    let valid = query!(
+        r#"
+            WITH RECURSIVE chain AS (
+                SELECT id,parent_id FROM member WHERE id = $2 AND deleted_at IS NULL
+                UNION ALL
+                SELECT m.id,m.parent_id
+                FROM member m JOIN chain c ON m.id = c.parent_id
+                WHERE m.id <> uuid_nil()
+            )
+            SELECT EXISTS(SELECT 1 FROM chain)
+                AND NOT EXISTS(SELECT 1 FROM chain WHERE id = $1) as "valid!"
+            "#,
+        moved.id,
+        moved.parent_id
+    )
+    .fetch_one(&mut **transaction)
+    .await?
+    .valid;
+    if !valid {
+        anyhow::bail!(
+            "member {} can not be moved under {}",
+            moved.id,
+            moved.parent_id
+        );
+    }
+    query!(
+        "update member set parent_id = $2,version = version + 1 where id = $1",
+        moved.id,
+        moved.parent_id
+    )
+    .execute(&mut **transaction)
+    .await?;
+    let after = Snapshot {
+        parent_id: Some(moved.parent_id),
+        member: SonlessRawMember {
+            version: before.member.version + 1,
+            ..before.member.clone()
+        },
+    };
+    history::record(
+        transaction,
+        batch,
+        HistoryAction::Update,
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+    Ok(())
+}
+
+// folds the loser into the survivor, which takes the merged fields, the loser's sons,
+// marriages, claims and grants, then removes the loser
 #[cfg(feature = "ssr")]
-pub async fn delete(transaction: &mut Transaction<'_, Postgres>, id: Uuid) -> anyhow::Result<()> {
-    query!("delete from member where id = $1", id)
+pub async fn merge(
+    transaction: &mut Transaction<'_, Postgres>,
+    merged: SonlessRawMember,
+    loser: Uuid,
+    loser_version: i32,
+    batch: &Batch,
+) -> anyhow::Result<()> {
+    let survivor = merged.id;
+    if survivor == loser {
+        anyhow::bail!("member {survivor} can not be merged with itself");
+    }
+    let Some(before) = snapshots(transaction, loser, false).await?.pop() else {
+        anyhow::bail!("member {loser} not found");
+    };
+    if before.member.version != loser_version {
+        anyhow::bail!("member {loser} was changed by someone else");
+    }
+    let valid = query!(
+        r#"
+            WITH RECURSIVE chain AS (
+                SELECT id,parent_id FROM member WHERE id = $2 AND deleted_at IS NULL
+                UNION ALL
+                SELECT m.id,m.parent_id
+                FROM member m JOIN chain c ON m.id = c.parent_id
+                WHERE m.id <> uuid_nil()
+            )
+            SELECT EXISTS(SELECT 1 FROM chain)
+                AND NOT EXISTS(SELECT 1 FROM chain WHERE id = $1)
+                AND NOT EXISTS(
+                    SELECT 1 FROM member a JOIN member b ON a.name = b.name
+                    WHERE a.parent_id = $1 AND b.parent_id = $2
+                        AND a.deleted_at IS NULL AND b.deleted_at IS NULL
+                ) as "valid!"
+            "#,
+        loser,
+        survivor
+    )
+    .fetch_one(&mut **transaction)
+    .await?
+    .valid;
+    if !valid {
+        anyhow::bail!("member {loser} can not be merged into {survivor}");
+    }
+    let claimed = query!(
+        "select count(*) as \"count!\" from users where member_id = $1 or member_id = $2",
+        loser,
+        survivor
+    )
+    .fetch_one(&mut **transaction)
+    .await?
+    .count;
+    if claimed > 1 {
+        anyhow::bail!("members {loser} and {survivor} are both claimed");
+    }
+    let sons = query!(
+        r#"
+            SELECT id FROM member
+            WHERE parent_id = $1 AND deleted_at IS NULL
+            ORDER BY name
+            "#,
+        loser
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+    for son in sons {
+        let Some(before) = snapshots(transaction, son.id, false).await?.pop() else {
+            anyhow::bail!("member {} not found", son.id);
+        };
+        let after = Snapshot {
+            parent_id: Some(survivor),
+            member: SonlessRawMember {
+                version: before.member.version + 1,
+                ..before.member.clone()
+            },
+        };
+        history::record(
+            transaction,
+            batch,
+            HistoryAction::Update,
+            Some(&before),
+            Some(&after),
+        )
+        .await?;
+    }
+    query!(
+        r#"
+            update member set parent_id = $2,
+                version = version + CASE WHEN deleted_at IS NULL THEN 1 ELSE 0 END
+            where parent_id = $1
+            "#,
+        loser,
+        survivor
+    )
+    .execute(&mut **transaction)
+    .await?;
+    query!(
+        "update member set mother_id = $2 where mother_id = $1",
+        loser,
+        survivor
+    )
+    .execute(&mut **transaction)
+    .await?;
+    query!(
+        r#"
+            delete from marriage l using marriage s
+            where (l.husband_id = $1 AND s.husband_id = $2 AND l.wife_id = s.wife_id)
+                OR (l.wife_id = $1 AND s.wife_id = $2 AND l.husband_id = s.husband_id)
+            "#,
+        loser,
+        survivor
+    )
+    .execute(&mut **transaction)
+    .await?;
+    query!(
+        r#"
+            update marriage set husband_id = CASE WHEN husband_id = $1 THEN $2 ELSE husband_id END,
+                wife_id = CASE WHEN wife_id = $1 THEN $2 ELSE wife_id END
+            where husband_id = $1 OR wife_id = $1
+            "#,
+        loser,
+        survivor
+    )
+    .execute(&mut **transaction)
+    .await?;
+    query!(
+        "update users set member_id = $2 where member_id = $1",
+        loser,
+        survivor
+    )
+    .execute(&mut **transaction)
+    .await?;
+    query!(
+        r#"
+            update member_claims c set member_id = $2
+            where c.member_id = $1 AND NOT EXISTS(
+                SELECT 1 FROM member_claims o
+                WHERE o.member_id = $2 AND o.user_id = c.user_id AND o.status = c.status
+            )
+            "#,
+        loser,
+        survivor
+    )
+    .execute(&mut **transaction)
+    .await?;
+    query!(
+        r#"
+            update grants g set root_id = $2
+            where g.root_id = $1 AND NOT EXISTS(
+                SELECT 1 FROM grants o WHERE o.root_id = $2 AND o.user_id = g.user_id
+            )
+            "#,
+        loser,
+        survivor
+    )
+    .execute(&mut **transaction)
+    .await?;
+    query!("delete from member where id = $1", loser)
         .execute(&mut **transaction)
         .await?;
+    update(transaction, vec![merged], batch).await?;
+    let Some(after) = snapshots(transaction, survivor, false).await?.pop() else {
+        anyhow::bail!("member {survivor} not found");
+    };
+    history::record(
+        transaction,
+        batch,
+        HistoryAction::Merge,
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
     Ok(())
 }
 
+// pairs of live members with the same normalized name and gender whose parents,
+// or else grandparents, match by normalized name as well
 #[cfg(feature = "ssr")]
-pub async fn read(pool: &Pool<Postgres>, id: Uuid) -> anyhow::Result<RawMember> {
-    let sons_ids = query!(
-        "select id from member where parent_id = $1 and id <> uuid_nil()",
-        id
+pub async fn duplicates(pool: &Pool<Postgres>, limit: i64) -> anyhow::Result<Vec<(Uuid, Uuid)>> {
+    let records = query!(
+        r#"
+            SELECT a.id as first,b.id as second
+            FROM member a
+            JOIN member b ON normalize_arabic(a.name) = normalize_arabic(b.name)
+                AND a.is_male = b.is_male AND a.id < b.id
+            JOIN member pa ON pa.id = a.parent_id
+            JOIN member pb ON pb.id = b.parent_id
+            LEFT JOIN member ga ON ga.id = pa.parent_id
+            LEFT JOIN member gb ON gb.id = pb.parent_id
+            WHERE a.id <> uuid_nil() AND b.id <> uuid_nil()
+                AND a.deleted_at IS NULL AND b.deleted_at IS NULL
+                AND a.parent_id <> b.id AND b.parent_id <> a.id
+                AND (
+                    pa.id = pb.id
+                    OR (normalize_arabic(pa.name) = normalize_arabic(pb.name) AND (
+                        pa.parent_id = pb.parent_id
+                        OR normalize_arabic(ga.name) = normalize_arabic(gb.name)
+                    ))
+                )
+            ORDER BY a.name,a.id,b.id
+            LIMIT $1
+            "#,
+        limit
     )
     .fetch_all(pool)
+    .await?;
+    Ok(records.into_iter().map(|x| (x.first, x.second)).collect())
+}
+
+// moves the member and its descendants to the trash
+#[cfg(feature = "ssr")]
+pub async fn delete(
+    transaction: &mut Transaction<'_, Postgres>,
+    id: Uuid,
+    version: i32,
+    batch: &Batch,
+) -> anyhow::Result<()> {
+    use chrono::Utc;
+    let removed = snapshots(transaction, id, true).await?;
+    let Some(root) = removed.first() else {
+        anyhow::bail!("member {id} not found");
+    };
+    if root.member.version != version {
+        anyhow::bail!("member {id} was changed by someone else");
+    }
+    let ids = removed.iter().map(|x| x.member.id).collect::<Vec<_>>();
+    query!(
+        r#"
+            update member set deleted_at = $2,deletion_id = $3,deleted_by = $4
+            where id = ANY($1)
+            "#,
+        &ids,
+        Utc::now().naive_utc(),
+        Uuid::new_v4(),
+        batch.author_id
+    )
+    .execute(&mut **transaction)
+    .await?;
+    for snapshot in removed.iter() {
+        history::record(
+            transaction,
+            batch,
+            HistoryAction::Delete,
+            Some(snapshot),
+            None,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+// a member of an edit that no longer matches the stored tree,
+// yours is None when the edit deletes it and theirs when it is gone from the tree
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Conflict {
+    pub id: Uuid,
+    pub yours: Option<SonlessRawMember>,
+    pub theirs: Option<SonlessRawMember>,
+}
+
+// locks the edited members and reports every change made against an outdated version
+#[cfg(feature = "ssr")]
+pub async fn conflicts(
+    transaction: &mut Transaction<'_, Postgres>,
+    created: &[(Uuid, RawMember)],
+    updated: &[SonlessRawMember],
+    deleted: &[(Uuid, i32)],
+    moved: &[MovedMember],
+) -> anyhow::Result<Vec<Conflict>> {
+    use std::collections::HashMap;
+    let ids = updated
+        .iter()
+        .map(|x| x.id)
+        .chain(deleted.iter().map(|(id, _)| *id))
+        .chain(moved.iter().map(|x| x.id))
+        .collect::<Vec<_>>();
+    let current = query!(
+        r#"
+            SELECT id,name,is_male,parent_id as "parent_id?",mother_id,
+                birth_date,birth_precision,death_date,death_precision,
+                kunya,laqab,nisba,alt_names,version
+            FROM member WHERE id = ANY($1) AND deleted_at IS NULL
+            FOR UPDATE
+            "#,
+        &ids
+    )
+    .fetch_all(&mut **transaction)
     .await?
     .into_iter()
-    .map(|x| x.id)
+    .map(|record| (record.id, (record.parent_id, sonless_raw_member!(record))))
+    .collect::<HashMap<_, _>>();
+
+    let mut conflicts = Vec::new();
+    for member in updated {
+        let theirs = match current.get(&member.id) {
+            None => None,
+            Some((_, theirs)) if theirs.version != member.version => Some(theirs.clone()),
+            Some(_) if moved.iter().any(|x| x.id == member.id) => continue,
+            Some((parent_id, _)) => {
+                let Some(sibling) = namesake(transaction, *parent_id, &member.name, &ids).await?
+                else {
+                    continue;
+                };
+                Some(sibling)
+            }
+        };
+        conflicts.push(Conflict {
+            id: member.id,
+            yours: Some(member.clone()),
+            theirs,
+        });
+    }
+    for (id, version) in deleted {
+        let theirs = match current.get(id) {
+            None => None,
+            Some((_, theirs)) if theirs.version != *version => Some(theirs.clone()),
+            Some(_) => continue,
+        };
+        conflicts.push(Conflict {
+            id: *id,
+            yours: None,
+            theirs,
+        });
+    }
+    for moved in moved {
+        if conflicts.iter().any(|x| x.id == moved.id) {
+            continue;
+        }
+        let yours = updated.iter().find(|x| x.id == moved.id).cloned();
+        let theirs = match current.get(&moved.id) {
+            None => None,
+            Some((_, theirs)) if theirs.version != moved.version => Some(theirs.clone()),
+            Some((_, stored)) => {
+                let name = yours.as_ref().map_or(&stored.name, |x| &x.name);
+                let Some(sibling) =
+                    namesake(transaction, Some(moved.parent_id), name, &ids).await?
+                else {
+                    continue;
+                };
+                Some(sibling)
+            }
+        };
+        let yours = yours.or_else(|| {
+            current.get(&moved.id).map(|(_, stored)| SonlessRawMember {
+                version: moved.version,
+                ..stored.clone()
+            })
+        });
+        conflicts.push(Conflict {
+            id: moved.id,
+            yours,
+            theirs,
+        });
+    }
+    for (parent_id, member) in created {
+        let parent_alive = query!(
+            r#"SELECT EXISTS(SELECT 1 FROM member WHERE id = $1 AND deleted_at IS NULL) as "alive!""#,
+            parent_id
+        )
+        .fetch_one(&mut **transaction)
+        .await?
+        .alive;
+        let theirs = if parent_alive {
+            let Some(sibling) = namesake(transaction, Some(*parent_id), &member.name, &ids).await?
+            else {
+                continue;
+            };
+            Some(sibling)
+        } else {
+            None
+        };
+        let RawMember {
+            id,
+            name,
+            is_male,
+            mother_id,
+            birth,
+            death,
+            names,
+            version,
+            ..
+        } = member.clone();
+        conflicts.push(Conflict {
+            id,
+            yours: Some(SonlessRawMember {
+                id,
+                name,
+                is_male,
+                mother_id,
+                birth,
+                death,
+                names,
+                version,
+            }),
+            theirs,
+        });
+    }
+    Ok(conflicts)
+}
+
+// a live son of the parent already carrying the name, other than the given members
+#[cfg(feature = "ssr")]
+async fn namesake(
+    transaction: &mut Transaction<'_, Postgres>,
+    parent_id: Option<Uuid>,
+    name: &str,
+    except: &[Uuid],
+) -> anyhow::Result<Option<SonlessRawMember>> {
+    let sibling = query!(
+        r#"
+            SELECT id,name,is_male,mother_id,
+                birth_date,birth_precision,death_date,death_precision,
+                kunya,laqab,nisba,alt_names,version
+            FROM member
+            WHERE parent_id = $1 AND name = $2 AND deleted_at IS NULL AND id <> ALL($3)
+            LIMIT 1
+            "#,
+        parent_id,
+        name,
+        except
+    )
+    .fetch_optional(&mut **transaction)
+    .await?
+    .map(|record| sonless_raw_member!(record));
+    Ok(sibling)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Trashed {
+    pub deletion_id: Uuid,
+    pub member: SonlessRawMember,
+    pub parent_id: Uuid,
+    pub deleted_at: chrono::NaiveDateTime,
+    pub deleted_by: Option<String>,
+    pub members: i64,
+}
+
+// the topmost member of every deletion still in the trash
+#[cfg(feature = "ssr")]
+pub async fn trash(pool: &Pool<Postgres>) -> anyhow::Result<Vec<Trashed>> {
+    let trash = query!(
+        r#"
+            SELECT m.id,m.name,m.is_male,m.mother_id,
+                m.birth_date,m.birth_precision,m.death_date,m.death_precision,
+                m.kunya,m.laqab,m.nisba,m.alt_names,m.version,
+                m.parent_id as "parent_id!",m.deletion_id as "deletion_id!",
+                m.deleted_at as "deleted_at!",u.username as "username?",
+                (SELECT count(*) FROM member d WHERE d.deletion_id = m.deletion_id) as "members!"
+            FROM member m JOIN member p ON p.id = m.parent_id
+            LEFT JOIN users u ON u.id = m.deleted_by
+            WHERE m.deletion_id IS NOT NULL AND p.deletion_id IS DISTINCT FROM m.deletion_id
+            ORDER BY m.deleted_at DESC
+            "#
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| Trashed {
+        deletion_id: record.deletion_id,
+        parent_id: record.parent_id,
+        deleted_at: record.deleted_at,
+        deleted_by: record.username,
+        members: record.members,
+        member: sonless_raw_member!(record),
+    })
+    .collect();
+    Ok(trash)
+}
+
+// the trash entry a deleted member belongs to
+#[cfg(feature = "ssr")]
+pub async fn deletion(pool: &Pool<Postgres>, id: Uuid) -> anyhow::Result<Option<Uuid>> {
+    let record = query!("SELECT deletion_id FROM member WHERE id = $1", id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(record.and_then(|x| x.deletion_id))
+}
+
+// the deleted members of a trash entry, topmost first
+#[cfg(feature = "ssr")]
+async fn trashed(
+    transaction: &mut Transaction<'_, Postgres>,
+    deletion_id: Uuid,
+) -> anyhow::Result<Vec<Snapshot>> {
+    let snapshots = query!(
+        r#"
+            WITH RECURSIVE tree AS (
+                SELECT m.id,0 AS depth FROM member m JOIN member p ON p.id = m.parent_id
+                WHERE m.deletion_id = $1 AND p.deletion_id IS DISTINCT FROM m.deletion_id
+                UNION ALL
+                SELECT m.id,t.depth + 1
+                FROM member m JOIN tree t ON m.parent_id = t.id
+                WHERE m.deletion_id = $1
+            )
+            SELECT m.id,m.name,m.is_male,m.parent_id as "parent_id?",m.mother_id,
+                m.birth_date,m.birth_precision,m.death_date,m.death_precision,
+                m.kunya,m.laqab,m.nisba,m.alt_names,m.version
+            FROM tree t JOIN member m ON m.id = t.id ORDER BY t.depth
+            FOR UPDATE OF m
+            "#,
+        deletion_id
+    )
+    .fetch_all(&mut **transaction)
+    .await?
+    .into_iter()
+    .map(|record| Snapshot {
+        parent_id: record.parent_id,
+        member: sonless_raw_member!(record),
+    })
     .collect::<Vec<_>>();
-    let mut sons = Vec::new();
-    for son_id in sons_ids {
-        let son = Box::pin(read(pool, son_id)).await?;
-        sons.push(son);
+    if snapshots.is_empty() {
+        anyhow::bail!("deletion {deletion_id} not found");
     }
-    let member_record = query!("select name,is_male from member where id = $1", id)
-        .fetch_one(pool)
+    Ok(snapshots)
+}
+
+// the parent of the topmost member of a trash entry
+#[cfg(feature = "ssr")]
+pub async fn trash_parent(
+    transaction: &mut Transaction<'_, Postgres>,
+    deletion_id: Uuid,
+) -> anyhow::Result<Option<Uuid>> {
+    Ok(trashed(transaction, deletion_id)
+        .await?
+        .first()
+        .and_then(|x| x.parent_id))
+}
+
+#[cfg(feature = "ssr")]
+pub async fn restore(
+    transaction: &mut Transaction<'_, Postgres>,
+    deletion_id: Uuid,
+    batch: &Batch,
+) -> anyhow::Result<()> {
+    let restored = trashed(transaction, deletion_id).await?;
+    let parent_deleted = query!(
+        r#"SELECT deleted_at IS NOT NULL as "deleted!" FROM member WHERE id = $1"#,
+        restored[0].parent_id
+    )
+    .fetch_one(&mut **transaction)
+    .await?
+    .deleted;
+    if parent_deleted {
+        anyhow::bail!("the parent of deletion {deletion_id} is deleted");
+    }
+    query!(
+        r#"
+            update member set deleted_at = NULL,deletion_id = NULL,deleted_by = NULL
+            where deletion_id = $1
+            "#,
+        deletion_id
+    )
+    .execute(&mut **transaction)
+    .await?;
+    for snapshot in restored.iter() {
+        history::record(
+            transaction,
+            batch,
+            HistoryAction::Restore,
+            None,
+            Some(snapshot),
+        )
         .await?;
-    let name: String = member_record.name;
-    let is_male: bool = member_record.is_male;
-    Ok(RawMember {
+    }
+    Ok(())
+}
+
+// permanently removes a trash entry deleted more than retention_days ago
+#[cfg(feature = "ssr")]
+pub async fn purge(
+    transaction: &mut Transaction<'_, Postgres>,
+    deletion_id: Uuid,
+    retention_days: i32,
+    batch: &Batch,
+) -> anyhow::Result<()> {
+    let purged = trashed(transaction, deletion_id).await?;
+    let ids = purged.iter().map(|x| x.member.id).collect::<Vec<_>>();
+    // deleting the rows cascades to every descendant, so those trashed in another
+    // deletion have to be purged first
+    let others = query!(
+        r#"
+            WITH RECURSIVE tree AS (
+                SELECT id FROM member WHERE id = ANY($1)
+                UNION ALL
+                SELECT m.id FROM member m JOIN tree t ON m.parent_id = t.id
+                WHERE m.id <> uuid_nil()
+            )
+            SELECT count(*) as "count!" FROM tree t JOIN member m ON m.id = t.id
+            WHERE m.deletion_id IS DISTINCT FROM $2
+            "#,
+        &ids,
+        deletion_id
+    )
+    .fetch_one(&mut **transaction)
+    .await?
+    .count;
+    if others > 0 {
+        anyhow::bail!("deletion {deletion_id} has descendants outside of it");
+    }
+    query!(
+        "update users set member_id = NULL where member_id = ANY($1)",
+        &ids
+    )
+    .execute(&mut **transaction)
+    .await?;
+    let result = query!(
+        r#"
+            delete from member
+            where id = ANY($1)
+                AND deleted_at < (now() at time zone 'utc') - make_interval(days => $2)
+            "#,
+        &ids,
+        retention_days
+    )
+    .execute(&mut **transaction)
+    .await?;
+    if result.rows_affected() != ids.len() as u64 {
+        anyhow::bail!("deletion {deletion_id} is still within the retention period");
+    }
+    for snapshot in purged.iter() {
+        history::record(
+            transaction,
+            batch,
+            HistoryAction::Purge,
+            Some(snapshot),
+            None,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+// the member with its parent id, followed by all its descendants when asked
+#[cfg(feature = "ssr")]
+async fn snapshots(
+    transaction: &mut Transaction<'_, Postgres>,
+    id: Uuid,
+    descendants: bool,
+) -> anyhow::Result<Vec<Snapshot>> {
+    let snapshots = query!(
+        r#"
+            WITH RECURSIVE tree AS (
+                SELECT id,0 AS depth FROM member WHERE id = $1 AND deleted_at IS NULL
+                UNION ALL
+                SELECT m.id,t.depth + 1
+                FROM member m JOIN tree t ON m.parent_id = t.id
+                WHERE m.id <> uuid_nil() AND m.deleted_at IS NULL AND $2
+            )
+            SELECT m.id,m.name,m.is_male,m.parent_id as "parent_id?",m.mother_id,
+                m.birth_date,m.birth_precision,m.death_date,m.death_precision,
+                m.kunya,m.laqab,m.nisba,m.alt_names,m.version
+            FROM tree t JOIN member m ON m.id = t.id ORDER BY t.depth
+            FOR UPDATE OF m
+            "#,
         id,
-        name,
-        is_male,
-        sons,
+        descendants
+    )
+    .fetch_all(&mut **transaction)
+    .await?
+    .into_iter()
+    .map(|record| Snapshot {
+        parent_id: record.parent_id,
+        member: sonless_raw_member!(record),
+    })
+    .collect();
+    Ok(snapshots)
+}
+
+#[cfg(feature = "ssr")]
+pub async fn read(
+    pool: &Pool<Postgres>,
+    id: Uuid,
+    max_depth: Option<i32>,
+) -> anyhow::Result<RawMember> {
+    use std::collections::HashMap;
+    let records = query!(
+        r#"
+            WITH RECURSIVE tree AS (
+                SELECT id,0 AS depth FROM member WHERE id = $1 AND deleted_at IS NULL
+                UNION ALL
+                SELECT m.id,t.depth + 1
+                FROM member m JOIN tree t ON m.parent_id = t.id
+                WHERE m.id <> uuid_nil() AND m.deleted_at IS NULL AND ($2::INT IS NULL OR t.depth < $2)
+            )
+            SELECT m.id,m.name,m.is_male,m.parent_id,m.mother_id,
+                m.birth_date,m.birth_precision,m.death_date,m.death_precision,
+                m.kunya,m.laqab,m.nisba,m.alt_names,m.version,
+                ($2::INT IS NOT NULL AND t.depth >= $2 AND EXISTS(
+                    SELECT 1 FROM member c
+                    WHERE c.parent_id = t.id AND c.id <> uuid_nil() AND c.deleted_at IS NULL
+                )) as "unloaded_sons!"
+            FROM tree t JOIN member m ON m.id = t.id ORDER BY t.depth
+            "#,
+        id,
+        max_depth
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut records = records.into_iter().map(|record| {
+        let parent_id = record.parent_id;
+        let unloaded_sons = record.unloaded_sons;
+        let member = sonless_raw_member!(record);
+        (parent_id, member.with_sons(Vec::new(), unloaded_sons))
+    });
+    let Some((_, root)) = records.next() else {
+        anyhow::bail!("member {id} not found");
+    };
+    let mut children = HashMap::<Uuid, Vec<RawMember>>::new();
+    for (parent_id, member) in records {
+        children.entry(parent_id).or_default().push(member);
+    }
+
+    fn build(mut member: RawMember, children: &mut HashMap<Uuid, Vec<RawMember>>) -> RawMember {
+        member.sons = children
+            .remove(&member.id)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|son| build(son, children))
+            .collect();
+        member
+    }
+
+    Ok(build(root, &mut children))
+}
+
+// the given ids that are taken by a member, trashed ones included
+#[cfg(feature = "ssr")]
+pub async fn existing(pool: &Pool<Postgres>, ids: &[Uuid]) -> anyhow::Result<Vec<Uuid>> {
+    let existing = query!("SELECT id FROM member WHERE id = ANY($1)", ids)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|x| x.id)
+        .collect();
+    Ok(existing)
+}
+
+// the members carrying one of the given names
+#[cfg(feature = "ssr")]
+pub async fn named(pool: &Pool<Postgres>, names: &[String]) -> anyhow::Result<Vec<(Uuid, String)>> {
+    let named = query!(
+        "SELECT id,name FROM member WHERE name = ANY($1) AND id <> uuid_nil() AND deleted_at IS NULL",
+        names
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| (x.id, x.name))
+    .collect();
+    Ok(named)
+}
+
+// the names taken under each of the given parents, trashed sons leave theirs free
+#[cfg(feature = "ssr")]
+pub async fn son_names(
+    pool: &Pool<Postgres>,
+    parent_ids: &[Uuid],
+) -> anyhow::Result<Vec<(Uuid, String)>> {
+    let names = query!(
+        r#"
+            SELECT parent_id,name FROM member
+            WHERE parent_id = ANY($1) AND id <> uuid_nil() AND deleted_at IS NULL
+            "#,
+        parent_ids
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| (x.parent_id, x.name))
+    .collect();
+    Ok(names)
+}
+
+#[cfg(feature = "ssr")]
+pub async fn ancestors(pool: &Pool<Postgres>, id: Uuid) -> anyhow::Result<Vec<SonlessRawMember>> {
+    let chain = query!(
+        r#"
+            WITH RECURSIVE chain AS (
+                SELECT id,parent_id,0 AS depth FROM member WHERE id = $1 AND deleted_at IS NULL
+                UNION ALL
+                SELECT m.id,m.parent_id,c.depth + 1
+                FROM member m JOIN chain c ON m.id = c.parent_id
+                WHERE m.id <> uuid_nil()
+            )
+            SELECT m.id,m.name,m.is_male,m.mother_id,
+                m.birth_date,m.birth_precision,m.death_date,m.death_precision,
+                m.kunya,m.laqab,m.nisba,m.alt_names,m.version
+            FROM chain c JOIN member m ON m.id = c.id ORDER BY c.depth
+            "#,
+        id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| sonless_raw_member!(record))
+    .collect::<Vec<_>>();
+    if chain.is_empty() {
+        anyhow::bail!("member {id} not found");
+    }
+    Ok(chain)
+}
+
+#[cfg(feature = "ssr")]
+pub async fn search(
+    pool: &Pool<Postgres>,
+    name: &str,
+    limit: i64,
+) -> anyhow::Result<Vec<Vec<SonlessRawMember>>> {
+    let pattern = format!(
+        "%{}%",
+        name.trim()
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    );
+    let records = query!(
+        r#"
+            WITH RECURSIVE hits AS (
+                SELECT id,row_number() OVER (
+                    ORDER BY normalize_arabic(name) = normalize_arabic($1) DESC,
+                        normalize_arabic(name) LIKE normalize_arabic($2) DESC,length(name),name
+                ) AS rank
+                FROM member
+                WHERE id <> uuid_nil() AND deleted_at IS NULL AND (
+                    normalize_arabic(name) LIKE normalize_arabic($2)
+                    OR member_other_names(kunya,laqab,nisba,alt_names) LIKE normalize_arabic($2)
+                )
+                ORDER BY rank LIMIT $3
+            ), chain AS (
+                SELECT h.rank,m.id,m.parent_id,0 AS depth
+                FROM hits h JOIN member m ON m.id = h.id
+                UNION ALL
+                SELECT c.rank,m.id,m.parent_id,c.depth + 1
+                FROM member m JOIN chain c ON m.id = c.parent_id
+                WHERE m.id <> uuid_nil()
+            )
+            SELECT c.rank as "rank!",m.id,m.name,m.is_male,m.mother_id,
+                m.birth_date,m.birth_precision,m.death_date,m.death_precision,
+                m.kunya,m.laqab,m.nisba,m.alt_names,m.version
+            FROM chain c JOIN member m ON m.id = c.id ORDER BY c.rank,c.depth
+            "#,
+        name.trim(),
+        pattern,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut hits: Vec<(i64, Vec<SonlessRawMember>)> = Vec::new();
+    for record in records {
+        let hit = record.rank;
+        let member = sonless_raw_member!(record);
+        match hits.last_mut() {
+            Some((rank, chain)) if *rank == hit => chain.push(member),
+            _ => hits.push((hit, vec![member])),
+        }
+    }
+    Ok(hits.into_iter().map(|(_, chain)| chain).collect())
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Stats {
+    pub members: i64,
+    pub generations: i64,
+    pub recently_added: i64,
+}
+
+#[cfg(feature = "ssr")]
+pub async fn stats(pool: &Pool<Postgres>, recent_days: i32) -> anyhow::Result<Stats> {
+    let record = query!(
+        r#"
+            WITH RECURSIVE tree AS (
+                SELECT id,1 AS depth FROM member
+                WHERE parent_id = uuid_nil() AND id <> uuid_nil() AND deleted_at IS NULL
+                UNION ALL
+                SELECT m.id,t.depth + 1
+                FROM member m JOIN tree t ON m.parent_id = t.id
+                WHERE m.id <> uuid_nil() AND m.deleted_at IS NULL
+            )
+            SELECT
+                (SELECT count(*) FROM member WHERE id <> uuid_nil() AND deleted_at IS NULL) as "members!",
+                (SELECT coalesce(max(depth),0) FROM tree) as "generations!",
+                (SELECT count(*) FROM member
+                    WHERE id <> uuid_nil() AND deleted_at IS NULL
+                    AND insert_date > (now() at time zone 'utc') - make_interval(days => $1)
+                ) as "recently_added!"
+            "#,
+        recent_days
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(Stats {
+        members: record.members,
+        generations: record.generations as i64,
+        recently_added: record.recently_added,
     })
 }
+
+#[cfg(feature = "ssr")]
+pub async fn families(pool: &Pool<Postgres>) -> anyhow::Result<Vec<(SonlessRawMember, i64)>> {
+    let families = query!(
+        r#"
+            WITH RECURSIVE tree AS (
+                SELECT m.id AS family_id,m.id
+                FROM member m JOIN member r ON m.parent_id = r.id
+                WHERE r.parent_id = uuid_nil() AND r.id <> uuid_nil() AND m.id <> uuid_nil()
+                    AND r.deleted_at IS NULL AND m.deleted_at IS NULL
+                UNION ALL
+                SELECT t.family_id,m.id
+                FROM member m JOIN tree t ON m.parent_id = t.id
+                WHERE m.id <> uuid_nil() AND m.deleted_at IS NULL
+            )
+            SELECT f.id,f.name,f.is_male,f.mother_id,
+                f.birth_date,f.birth_precision,f.death_date,f.death_precision,
+                f.kunya,f.laqab,f.nisba,f.alt_names,f.version,
+                count(*) as "members!"
+            FROM tree t JOIN member f ON f.id = t.family_id
+            GROUP BY f.id ORDER BY count(*) DESC,f.name
+            "#
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| {
+        let members = record.members;
+        (sonless_raw_member!(record), members)
+    })
+    .collect();
+    Ok(families)
+}
+
+#[cfg(feature = "ssr")]
+pub async fn recent(pool: &Pool<Postgres>, limit: i64) -> anyhow::Result<Vec<SonlessRawMember>> {
+    let members = query!(
+        r#"
+            SELECT id,name,is_male,mother_id,
+                birth_date,birth_precision,death_date,death_precision,
+                kunya,laqab,nisba,alt_names,version
+            FROM member
+            WHERE id <> uuid_nil() AND deleted_at IS NULL
+            ORDER BY insert_date DESC LIMIT $1
+            "#,
+        limit
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| sonless_raw_member!(record))
+    .collect();
+    Ok(members)
+}
+
+#[cfg(feature = "ssr")]
+pub async fn wives(pool: &Pool<Postgres>, husband: Uuid) -> anyhow::Result<Vec<SonlessRawMember>> {
+    let wives = query!(
+        r#"
+            SELECT w.id,w.name,w.is_male,w.mother_id,
+                w.birth_date,w.birth_precision,w.death_date,w.death_precision,
+                w.kunya,w.laqab,w.nisba,w.alt_names,w.version
+            FROM member w WHERE w.deleted_at IS NULL AND w.id IN (
+                SELECT mother_id FROM member WHERE parent_id = $1
+                UNION
+                SELECT wife_id FROM marriage WHERE husband_id = $1
+            )
+            "#,
+        husband
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| sonless_raw_member!(record))
+    .collect();
+    Ok(wives)
+}
+
+#[cfg(feature = "ssr")]
+pub async fn read_sonless(
+    pool: &Pool<Postgres>,
+    ids: &[Uuid],
+) -> anyhow::Result<Vec<SonlessRawMember>> {
+    let members = query!(
+        r#"
+            SELECT id,name,is_male,mother_id,
+                birth_date,birth_precision,death_date,death_precision,
+                kunya,laqab,nisba,alt_names,version
+            FROM member WHERE id = ANY($1) AND deleted_at IS NULL
+            "#,
+        ids
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| sonless_raw_member!(record))
+    .collect();
+    Ok(members)
+}
@@ -0,0 +1,149 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ssr")]
+use sqlx::{query, Pool, Postgres, Transaction};
+#[cfg(feature = "ssr")]
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum ProposalStatus {
+    #[default]
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl ProposalStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProposalStatus::Pending => "pending",
+            ProposalStatus::Approved => "approved",
+            ProposalStatus::Rejected => "rejected",
+        }
+    }
+}
+
+impl FromStr for ProposalStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(ProposalStatus::Pending),
+            "approved" => Ok(ProposalStatus::Approved),
+            "rejected" => Ok(ProposalStatus::Rejected),
+            _ => Err(anyhow::anyhow!("unknown proposal status {s}")),
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub struct Proposal<T> {
+    pub id: Uuid,
+    pub author_id: Uuid,
+    pub author: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub status: ProposalStatus,
+    pub updates: T,
+}
+
+#[cfg(feature = "ssr")]
+pub async fn create<T: Serialize>(
+    pool: &Pool<Postgres>,
+    author_id: Uuid,
+    updates: &T,
+) -> anyhow::Result<Uuid> {
+    use chrono::Utc;
+    let id = Uuid::new_v4();
+    query!(
+        r#"
+            INSERT INTO proposals (id,author_id,updates,created_at)
+            VALUES($1,$2,$3,$4);
+            "#,
+        id,
+        author_id,
+        serde_json::to_value(updates)?,
+        Utc::now().naive_utc()
+    )
+    .execute(pool)
+    .await?;
+    Ok(id)
+}
+
+#[cfg(feature = "ssr")]
+pub async fn pending<T: serde::de::DeserializeOwned>(
+    pool: &Pool<Postgres>,
+) -> anyhow::Result<Vec<Proposal<T>>> {
+    let records = query!(
+        r#"
+            SELECT p.id,p.author_id,u.username,p.created_at,p.status,p.updates
+            FROM proposals p JOIN users u ON u.id = p.author_id
+            WHERE p.status = 'pending' ORDER BY p.created_at
+            "#
+    )
+    .fetch_all(pool)
+    .await?;
+    let mut proposals = Vec::new();
+    for record in records {
+        proposals.push(Proposal {
+            id: record.id,
+            author_id: record.author_id,
+            author: record.username,
+            created_at: record.created_at,
+            status: record.status.parse()?,
+            updates: serde_json::from_value(record.updates)?,
+        });
+    }
+    Ok(proposals)
+}
+
+#[cfg(feature = "ssr")]
+pub async fn read<T: serde::de::DeserializeOwned>(
+    pool: &Pool<Postgres>,
+    id: Uuid,
+) -> anyhow::Result<Proposal<T>> {
+    let record = query!(
+        r#"
+            SELECT p.id,p.author_id,u.username,p.created_at,p.status,p.updates
+            FROM proposals p JOIN users u ON u.id = p.author_id
+            WHERE p.id = $1
+            "#,
+        id
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(Proposal {
+        id: record.id,
+        author_id: record.author_id,
+        author: record.username,
+        created_at: record.created_at,
+        status: record.status.parse()?,
+        updates: serde_json::from_value(record.updates)?,
+    })
+}
+
+#[cfg(feature = "ssr")]
+pub async fn review(
+    transaction: &mut Transaction<'_, Postgres>,
+    id: Uuid,
+    status: ProposalStatus,
+    reviewer_id: Uuid,
+) -> anyhow::Result<()> {
+    use chrono::Utc;
+    let result = query!(
+        r#"
+            update proposals set status = $2,reviewer_id = $3,reviewed_at = $4
+            where id = $1 and status = 'pending'
+            "#,
+        id,
+        status.as_str(),
+        reviewer_id,
+        Utc::now().naive_utc()
+    )
+    .execute(&mut **transaction)
+    .await?;
+    if result.rows_affected() == 0 {
+        anyhow::bail!("proposal {id} was already reviewed");
+    }
+    Ok(())
+}
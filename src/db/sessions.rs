@@ -0,0 +1,59 @@
+#[cfg(feature = "ssr")]
+use sqlx::{query, query_as, Pool, Postgres};
+#[cfg(feature = "ssr")]
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+use super::users::{Role, User};
+
+#[cfg(feature = "ssr")]
+pub async fn create(pool: &Pool<Postgres>, user_id: Uuid, days: i64) -> anyhow::Result<Uuid> {
+    use chrono::{Duration, Utc};
+    let id = Uuid::new_v4();
+    let now = Utc::now().naive_utc();
+    query!(
+        r#"
+            INSERT INTO sessions (id,user_id,created_at,expires_at)
+            VALUES($1,$2,$3,$4);
+            "#,
+        id,
+        user_id,
+        now,
+        now + Duration::days(days)
+    )
+    .execute(pool)
+    .await?;
+    Ok(id)
+}
+
+#[cfg(feature = "ssr")]
+pub async fn user(pool: &Pool<Postgres>, id: Uuid) -> anyhow::Result<Option<User>> {
+    let user = query_as!(
+        User,
+        r#"
+            SELECT u.id,u.username,u.password,u.member_id,u.role as "role: Role",u.active
+            FROM sessions s JOIN users u ON u.id = s.user_id
+            WHERE s.id = $1 AND u.active AND s.expires_at > (now() at time zone 'utc')
+            "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(user)
+}
+
+#[cfg(feature = "ssr")]
+pub async fn delete(pool: &Pool<Postgres>, id: Uuid) -> anyhow::Result<()> {
+    query!("delete from sessions where id = $1", id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn delete_expired(pool: &Pool<Postgres>) -> anyhow::Result<()> {
+    query!("delete from sessions where expires_at <= (now() at time zone 'utc')")
+        .execute(pool)
+        .await?;
+    Ok(())
+}
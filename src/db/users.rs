@@ -3,17 +3,123 @@ use anyhow::Ok;
 #[cfg(feature = "ssr")]
 use sqlx::{query, query_as, Pool, Postgres};
 
+use std::str::FromStr;
+
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "ssr", derive(sqlx::Type))]
+#[cfg_attr(
+    feature = "ssr",
+    sqlx(type_name = "varchar", rename_all = "snake_case")
+)]
+pub enum Role {
+    #[default]
+    Viewer,
+    Editor,
+    BranchAdmin,
+    SuperAdmin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Editor => "editor",
+            Role::BranchAdmin => "branch_admin",
+            Role::SuperAdmin => "super_admin",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Role::Viewer => "مشاهد",
+            Role::Editor => "محرر",
+            Role::BranchAdmin => "مشرف فرع",
+            Role::SuperAdmin => "مشرف عام",
+        }
+    }
+
+    pub fn can_edit(&self) -> bool {
+        *self != Role::Viewer
+    }
+}
+
+impl FromStr for Role {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "viewer" => Ok(Role::Viewer),
+            "editor" => Ok(Role::Editor),
+            "branch_admin" => Ok(Role::BranchAdmin),
+            "super_admin" => Ok(Role::SuperAdmin),
+            _ => Err(anyhow::anyhow!("unknown role {s}")),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct User {
     pub id: Uuid,
     pub username: String,
     pub password: String,
     pub member_id: Option<Uuid>,
+    pub role: Role,
+    pub active: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct UserInfo {
+    pub id: Uuid,
+    pub username: String,
+    pub member_id: Option<Uuid>,
+    pub role: Role,
+    pub active: bool,
+}
+
+impl User {
+    pub fn info(&self) -> UserInfo {
+        UserInfo {
+            id: self.id,
+            username: self.username.clone(),
+            member_id: self.member_id,
+            role: self.role,
+            active: self.active,
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub fn hash_password(password: &str) -> anyhow::Result<String> {
+    use argon2::{
+        password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
+        Argon2,
+    };
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|err| anyhow::anyhow!("{err}"))?;
+    Ok(hash.to_string())
+}
+
+#[cfg(feature = "ssr")]
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    use argon2::{
+        password_hash::{PasswordHash, PasswordVerifier},
+        Argon2,
+    };
+    let Some(hash) = PasswordHash::new(hash).ok() else {
+        return false;
+    };
+    Argon2::default()
+        .verify_password(password.as_bytes(), &hash)
+        .is_ok()
 }
 
+// the first account of a fresh deployment becomes its super admin, as nobody else
+// could hand out roles, returns the role the user was stored with
 #[cfg(feature = "ssr")]
 pub async fn create(
     User {
@@ -21,22 +127,35 @@ pub async fn create(
         username,
         password,
         member_id,
+        role,
+        active,
     }: User,
     pool: &Pool<Postgres>,
-) -> anyhow::Result<()> {
-    query!(
+) -> anyhow::Result<Role> {
+    let password = hash_password(&password)?;
+    let mut transaction = pool.begin().await?;
+    query!("LOCK TABLE users IN SHARE ROW EXCLUSIVE MODE")
+        .execute(&mut *transaction)
+        .await?;
+    let role = query!(
         r#"
-            INSERT INTO users (id,username,password,member_id)
-            VALUES($1,$2,$3,$4);
+            INSERT INTO users (id,username,password,member_id,role,active)
+            VALUES($1,$2,$3,$4,
+                CASE WHEN EXISTS(SELECT 1 FROM users) THEN $5 ELSE 'super_admin' END,$6)
+            RETURNING role
             "#,
         id,
         username,
         password,
-        member_id
+        member_id,
+        role.as_str(),
+        active
     )
-    .execute(pool)
-    .await?;
-    Ok(())
+    .fetch_one(&mut *transaction)
+    .await?
+    .role;
+    transaction.commit().await?;
+    Role::from_str(&role)
 }
 
 #[cfg(feature = "ssr")]
@@ -45,18 +164,23 @@ pub async fn update(
     User {
         id,
         username,
-        password,
         member_id,
+        role,
+        active,
+        ..
     }: User,
 ) -> anyhow::Result<()> {
+    // the password only changes through set_password, User holds its hash
     query!(
         r#"
-            update users set username = $2,password = $3,member_id = $4 where id = $1
+            update users set username = $2,member_id = $3,role = $4,active = $5
+            where id = $1
             "#,
         id,
         username,
-        password,
-        member_id
+        member_id,
+        role.as_str(),
+        active
     )
     .execute(pool)
     .await?;
@@ -64,17 +188,192 @@ pub async fn update(
 }
 
 #[cfg(feature = "ssr")]
-pub async fn delete(pool: Pool<Postgres>, id: Uuid) -> anyhow::Result<()> {
+pub async fn delete(pool: &Pool<Postgres>, id: Uuid) -> anyhow::Result<()> {
     query!("delete from users where id = $1", id)
-        .execute(&pool)
+        .execute(pool)
         .await?;
     Ok(())
 }
 
 #[cfg(feature = "ssr")]
 pub async fn read(pool: &Pool<Postgres>, id: Uuid) -> anyhow::Result<User> {
-    let user = query_as!(User, "select * from users where id = $1", id)
-        .fetch_one(pool)
+    let user = query_as!(
+        User,
+        r#"select id,username,password,member_id,role as "role: Role",active from users where id = $1"#,
+        id
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(user)
+}
+
+#[cfg(feature = "ssr")]
+pub async fn authenticate(
+    pool: &Pool<Postgres>,
+    username: &str,
+    password: &str,
+) -> anyhow::Result<Option<User>> {
+    let user = query_as!(
+        User,
+        r#"select id,username,password,member_id,role as "role: Role",active from users where username = $1"#,
+        username
+    )
+    .fetch_optional(pool)
+    .await?;
+    let Some(user) = user.filter(|x| x.active) else {
+        return Ok(None);
+    };
+    if verify_password(password, &user.password) {
+        return Ok(Some(user));
+    }
+    // rows written before hashing was introduced still hold the plain password
+    if !user.password.starts_with('$') && user.password == password {
+        query!(
+            "update users set password = $2 where id = $1",
+            user.id,
+            hash_password(password)?
+        )
+        .execute(pool)
         .await?;
+        return Ok(Some(user));
+    }
+    Ok(None)
+}
+
+#[cfg(feature = "ssr")]
+pub async fn grant(pool: &Pool<Postgres>, user_id: Uuid, root_id: Uuid) -> anyhow::Result<()> {
+    query!(
+        "INSERT INTO grants (user_id,root_id) VALUES($1,$2) ON CONFLICT DO NOTHING",
+        user_id,
+        root_id
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn revoke(pool: &Pool<Postgres>, user_id: Uuid, root_id: Uuid) -> anyhow::Result<()> {
+    query!(
+        "delete from grants where user_id = $1 and root_id = $2",
+        user_id,
+        root_id
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn granted(pool: &Pool<Postgres>, root_id: Uuid) -> anyhow::Result<Vec<UserInfo>> {
+    let users = query_as!(
+        UserInfo,
+        r#"
+            SELECT u.id,u.username,u.member_id,u.role as "role: Role",u.active
+            FROM grants g JOIN users u ON u.id = g.user_id
+            WHERE g.root_id = $1 ORDER BY u.username
+            "#,
+        root_id
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(users)
+}
+
+// the subset of `ids` lying inside one of the subtrees granted to the user
+#[cfg(feature = "ssr")]
+pub async fn editable(
+    pool: &Pool<Postgres>,
+    user_id: Uuid,
+    ids: &[Uuid],
+) -> anyhow::Result<Vec<Uuid>> {
+    let editable = query!(
+        r#"
+            WITH RECURSIVE up AS (
+                SELECT id AS member_id,id,parent_id FROM member WHERE id = ANY($2)
+                UNION ALL
+                SELECT u.member_id,m.id,m.parent_id
+                FROM member m JOIN up u ON m.id = u.parent_id
+                WHERE m.id <> uuid_nil()
+            )
+            SELECT DISTINCT u.member_id as "member_id!"
+            FROM up u JOIN grants g ON g.root_id = u.id AND g.user_id = $1
+            "#,
+        user_id,
+        ids
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| x.member_id)
+    .collect();
+    Ok(editable)
+}
+
+#[cfg(feature = "ssr")]
+pub async fn find(pool: &Pool<Postgres>, username: &str) -> anyhow::Result<Option<UserInfo>> {
+    let user = query_as!(
+        UserInfo,
+        r#"select id,username,member_id,role as "role: Role",active from users where username = $1"#,
+        username
+    )
+    .fetch_optional(pool)
+    .await?;
     Ok(user)
 }
+
+#[cfg(feature = "ssr")]
+pub async fn list(pool: &Pool<Postgres>) -> anyhow::Result<Vec<UserInfo>> {
+    let users = query_as!(
+        UserInfo,
+        r#"select id,username,member_id,role as "role: Role",active from users order by username"#
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(users)
+}
+
+#[cfg(feature = "ssr")]
+pub async fn set_username(pool: &Pool<Postgres>, id: Uuid, username: &str) -> anyhow::Result<()> {
+    query!("update users set username = $2 where id = $1", id, username)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn set_password(pool: &Pool<Postgres>, id: Uuid, password: &str) -> anyhow::Result<()> {
+    query!(
+        "update users set password = $2 where id = $1",
+        id,
+        hash_password(password)?
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn set_role(pool: &Pool<Postgres>, id: Uuid, role: Role) -> anyhow::Result<()> {
+    query!(
+        "update users set role = $2 where id = $1",
+        id,
+        role.as_str()
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn set_active(pool: &Pool<Postgres>, id: Uuid, active: bool) -> anyhow::Result<()> {
+    query!("update users set active = $2 where id = $1", id, active)
+        .execute(pool)
+        .await?;
+    if !active {
+        query!("delete from sessions where user_id = $1", id)
+            .execute(pool)
+            .await?;
+    }
+    Ok(())
+}
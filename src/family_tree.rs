@@ -1,27 +1,84 @@
 use std::str::FromStr;
 
-use crate::db::member::RawMember;
+use crate::db::{marriage::Marriage, member::RawMember};
 use leptos::*;
 use leptos_router::use_params_map;
 use uuid::Uuid;
 
+mod bulk;
+mod claims;
+mod conflicts;
+mod duplicates;
+mod gedcom;
+mod history;
+mod import;
 mod member;
 mod member_actions;
+mod nasab;
+mod permissions;
+mod proposals;
+mod relationship;
+mod search;
+mod spreadsheet;
+#[cfg(feature = "ssr")]
+mod transfer;
+mod trash;
 use member::Member;
 use member_actions::*;
+pub use bulk::BulkImport;
+pub use claims::Claims;
+pub use duplicates::Duplicates;
+#[cfg(feature = "ssr")]
+pub use gedcom::export as export_gedcom;
+#[cfg(feature = "ssr")]
+pub use transfer::{export_cbor, export_json, import as import_tree};
+pub use history::History;
+pub use import::Import;
+pub use nasab::{Lineage, Nasab};
+pub use permissions::Permissions;
+pub use proposals::Proposals;
+pub use relationship::Relation;
+pub use search::MemberSearch;
+#[cfg(feature = "ssr")]
+pub use spreadsheet::export as export_csv;
+pub use spreadsheet::CsvImport;
+pub use trash::Trash;
 
-use self::member::Updates;
+use self::member::{Rm, Updates};
+
+#[cfg(feature = "ssr")]
+const LOAD_DEPTH: i32 = 3;
 
 #[server(encoding = "Cbor")]
 async fn get_member(id: Uuid) -> Result<RawMember, ServerFnError> {
     use crate::db::{member::read, Pool, Postgres};
     let pool = expect_context::<Pool<Postgres>>();
-    match read(&pool, id).await {
+    match read(&pool, id, Some(LOAD_DEPTH)).await {
         Ok(member) => Ok(member),
         Err(err) => Err(ServerFnError::ServerError(err.to_string())),
     }
 }
 
+#[server(encoding = "Cbor")]
+async fn get_sons(id: Uuid) -> Result<Vec<RawMember>, ServerFnError> {
+    use crate::db::{member::read, Pool, Postgres};
+    let pool = expect_context::<Pool<Postgres>>();
+    match read(&pool, id, Some(LOAD_DEPTH)).await {
+        Ok(member) => Ok(member.sons),
+        Err(err) => Err(ServerFnError::ServerError(err.to_string())),
+    }
+}
+
+#[server(encoding = "Cbor")]
+async fn get_marriages(ids: Vec<Uuid>) -> Result<Vec<Marriage>, ServerFnError> {
+    use crate::db::{marriage::read, Pool, Postgres};
+    let pool = expect_context::<Pool<Postgres>>();
+    match read(&pool, &ids).await {
+        Ok(marriages) => Ok(marriages),
+        Err(err) => Err(ServerFnError::ServerError(err.to_string())),
+    }
+}
+
 #[component]
 pub fn MemberNode() -> impl IntoView {
     let updates = RwSignal::new(Updates::default());
@@ -32,19 +89,35 @@ pub fn MemberNode() -> impl IntoView {
     };
 
     provide_context(member_actions::ActionsWaitlist::new());
+    provide_context(updates);
 
-    let member_resource = Resource::once(move || get_member(id()));
+    let member_resource = Resource::once(move || async move {
+        let member = get_member(id()).await?;
+        let marriages = get_marriages(member.ids()).await?;
+        Ok::<_, ServerFnError>((member, marriages))
+    });
     Effect::new(move |_| {
-        let member = member_resource
+        let (member, marriages) = member_resource
             .get()
             .and_then(Result::ok)
-            .map(Member::from_raw)
             .unwrap_or_default();
-        updates.set(member::Updates::init(member));
+        updates.set(member::Updates::init(Member::from_raw(member), marriages));
     });
+    let status = RwSignal::new(None::<&'static str>);
+    let conflicts = RwSignal::new(Vec::new());
     let save = move |_| {
         spawn_local(async move {
-            updates.get_untracked().commit().await.unwrap_or_default();
+            conflicts.set(Vec::new());
+            let message = match updates.get_untracked().commit().await {
+                Ok(member::Committed::Applied) => "تم الحفظ",
+                Ok(member::Committed::Proposed) => "تم ارسال التعديلات للمراجعة",
+                Ok(member::Committed::Conflicted(found)) => {
+                    conflicts.set(found);
+                    "تعذر الحفظ بسبب تعديلات اخرى"
+                }
+                Err(_) => "تعذر الحفظ",
+            };
+            status.set(Some(message));
         });
     };
 
@@ -60,20 +133,45 @@ pub fn MemberNode() -> impl IntoView {
                 <div class="grid justify-items-center overflow-auto">
                     <button on:click=save>"save"</button>
                     <button on:click=reset>"reset"</button>
+                    <a href=format!("/export/gedcom/{}", id()) download rel="external">
+                        "GEDCOM تنزيل الفرع بصيغة"
+                    </a>
+                    <a href=format!("/export/csv/{}", id()) download rel="external">
+                        "CSV تنزيل الفرع بصيغة"
+                    </a>
+                    <p>{move || status.get()}</p>
+                    <conflicts::Conflicts conflicts/>
                 </div>
             </section>
         }
     }
 }
 
+// fills in the sons of a member that were left unloaded, with their marriages
+async fn load_sons(updates: RwSignal<Updates>, member: Member) -> Result<(), ServerFnError> {
+    let sons = get_sons(member.id).await?;
+    let ids = sons.iter().flat_map(|son| son.ids()).collect();
+    let marriages = get_marriages(ids).await?;
+    updates.get_untracked().expand(member, sons, marriages);
+    Ok(())
+}
+
 #[component]
 fn Node(member: Member) -> impl IntoView {
+    let parent = use_context::<Member>();
     provide_context(member);
+    provide_context(member_actions::Parent(parent));
     let actions_waitlist = expect_context::<member_actions::ActionsWaitlist>();
+    let updates = expect_context::<RwSignal<Updates>>();
     let on_click = move |_| {
         member.action.set(member::Action::default());
         actions_waitlist.take(member.id);
     };
+    let expand = move |_| {
+        spawn_local(async move {
+            let _ = load_sons(updates, member).await;
+        });
+    };
 
     view! {
     <div class="flex flex-col my-10 flex-nowrap">
@@ -83,6 +181,24 @@ fn Node(member: Member) -> impl IntoView {
       >
         {move || member.name.get()}
       </button>
+      <div class="mx-5 text-sm text-center">
+        <For
+            each=move || updates.get().spouses(member.id)
+            key=|x| (x.id, x.status)
+            let:marriage
+        >
+          <Spouse marriage member_id=member.id/>
+        </For>
+      </div>
+      <Show
+          when=move || member.unloaded_sons.get()>
+        <button
+          on:click=expand
+          class="mx-5 text-sm rounded-lg border-2 border-gray-400 hover:border-gray-950"
+        >
+          عرض الابناء
+        </button>
+      </Show>
       <Show
           when=move || !member.sons.get().is_empty()>
         <div class="flex flex-row overflow-auto border-t-2 rounded-t-lg border-black">
@@ -99,3 +215,22 @@ fn Node(member: Member) -> impl IntoView {
     <Action/>
     }
 }
+
+#[component]
+fn Spouse(marriage: Marriage, member_id: Uuid) -> impl IntoView {
+    use crate::db::marriage::MarriageStatus;
+    let spouse = marriage.spouse_of(member_id).cloned().unwrap_or_default();
+    let status = match marriage.status {
+        MarriageStatus::Married => None,
+        MarriageStatus::Divorced => Some(" (طلاق)"),
+        MarriageStatus::Widowed => Some(" (وفاة)"),
+    };
+    view! {
+        <p>
+            <leptos_router::A href=format!("/exists/{}", spouse.id) class="hover:underline">
+                {spouse.name}
+            </leptos_router::A>
+            {status}
+        </p>
+    }
+}
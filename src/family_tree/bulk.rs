@@ -0,0 +1,311 @@
+use super::{member::Committed, search::MemberSearch};
+use crate::db::member::{RawMember, SonlessRawMember, NAME_LENGTH};
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Planned {
+    id: Uuid,
+    name: String,
+    existing: bool,
+    sons: Vec<Planned>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Plan {
+    tree: Planned,
+    errors: Vec<String>,
+}
+
+impl Planned {
+    fn too_long(&self) -> bool {
+        self.name.chars().count() > NAME_LENGTH
+    }
+
+    // (new, existing) members in the plan
+    fn count(&self) -> (usize, usize) {
+        let own = if self.existing { (0, 1) } else { (1, 0) };
+        self.sons
+            .iter()
+            .map(Planned::count)
+            .fold(own, |(new, existing), (x, y)| (new + x, existing + y))
+    }
+
+    fn member(&self, is_male: bool) -> RawMember {
+        RawMember {
+            id: self.id,
+            name: self.name.clone(),
+            is_male: is_male || !self.sons.is_empty(),
+            sons: self.sons.iter().map(|x| x.member(is_male)).collect(),
+            ..Default::default()
+        }
+    }
+
+    // the new members, each with the existing member it is added under
+    fn created(&self, is_male: bool) -> Vec<(Uuid, RawMember)> {
+        self.sons
+            .iter()
+            .flat_map(|son| match son.existing {
+                true => son.created(is_male),
+                false => vec![(self.id, son.member(is_male))],
+            })
+            .collect()
+    }
+}
+
+// one lineage per line or per comma, each written son first: ابن->اب->جد,
+// along with the number of its line
+#[cfg(feature = "ssr")]
+fn lineages(text: &str) -> Vec<(usize, Vec<String>)> {
+    text.lines()
+        .enumerate()
+        .flat_map(|(index, line)| line.split(',').map(move |x| (index + 1, x)))
+        .map(|(line, lineage)| {
+            let mut names = lineage
+                .split("->")
+                .map(str::trim)
+                .filter(|x| !x.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>();
+            names.reverse();
+            (line, names)
+        })
+        .filter(|(_, x)| !x.is_empty())
+        .collect()
+}
+
+#[cfg(feature = "ssr")]
+fn plant(node: &mut Planned, existing: Option<&RawMember>, path: &[String]) {
+    let Some((name, rest)) = path.split_first() else {
+        return;
+    };
+    let found = existing.and_then(|x| x.sons.iter().find(|son| &son.name == name));
+    let index = match node.sons.iter().position(|x| &x.name == name) {
+        Some(index) => index,
+        None => {
+            node.sons.push(Planned {
+                id: found.map_or_else(Uuid::new_v4, |x| x.id),
+                name: name.clone(),
+                existing: found.is_some(),
+                sons: Vec::new(),
+            });
+            node.sons.len() - 1
+        }
+    };
+    plant(&mut node.sons[index], found, rest);
+}
+
+#[server(encoding = "Cbor")]
+async fn plan_lineages(parent_id: Uuid, text: String) -> Result<Plan, ServerFnError> {
+    use crate::db::{member, Pool, Postgres};
+    crate::auth::require_user().await?;
+    let lineages = lineages(&text);
+    let depth = lineages.iter().map(|(_, x)| x.len()).max().unwrap_or(0);
+    let pool = expect_context::<Pool<Postgres>>();
+    let Ok(parent) = member::read(&pool, parent_id, Some(depth as i32)).await else {
+        return Err(ServerFnError::ServerError(
+            "error reading member".to_string(),
+        ));
+    };
+    let mut planned = Planned {
+        id: parent.id,
+        name: parent.name.clone(),
+        existing: true,
+        sons: Vec::new(),
+    };
+    let mut errors = Vec::new();
+    for (line, lineage) in lineages {
+        for name in lineage.iter() {
+            if name.chars().count() > NAME_LENGTH {
+                errors.push(format!(
+                    "السطر {line}: الاسم {name} اطول من {NAME_LENGTH} حرفا"
+                ));
+            }
+        }
+        plant(&mut planned, Some(&parent), &lineage);
+    }
+    Ok(Plan {
+        tree: planned,
+        errors,
+    })
+}
+
+#[server(encoding = "Cbor")]
+async fn import_lineages(created: Vec<(Uuid, RawMember)>) -> Result<Committed, ServerFnError> {
+    use super::member::{submit, ServerUpdates};
+    fn too_long(member: &RawMember) -> bool {
+        member.name.chars().count() > NAME_LENGTH || member.sons.iter().any(too_long)
+    }
+    let user = crate::auth::require_user().await?;
+    if created.iter().any(|(_, x)| too_long(x)) {
+        return Err(ServerFnError::ServerError("name too long".to_string()));
+    }
+    let updates = ServerUpdates {
+        created_members: created,
+        ..Default::default()
+    };
+    submit(&user, updates).await
+}
+
+#[component]
+pub fn BulkImport() -> impl IntoView {
+    let text = RwSignal::new(String::new());
+    let parent = RwSignal::new(None::<SonlessRawMember>);
+    let planned = RwSignal::new(None::<Plan>);
+    let status = RwSignal::new(None::<String>);
+    let select_ref = create_node_ref::<html::Select>();
+
+    let pick = Callback::new(move |member: SonlessRawMember| {
+        parent.set(Some(member));
+        planned.set(None);
+    });
+    let preview = move |_| {
+        let Some(parent) = parent.get_untracked() else {
+            return;
+        };
+        status.set(None);
+        spawn_local(async move {
+            match plan_lineages(parent.id, text.get_untracked()).await {
+                Ok(plan) => planned.set(Some(plan)),
+                Err(_) => status.set(Some("تعذرت قراءة الاسماء".to_string())),
+            }
+        });
+    };
+    let commit = move |_| {
+        let Some(plan) = planned.get_untracked().filter(|x| x.errors.is_empty()) else {
+            return;
+        };
+        let is_male: bool = select_ref.get().unwrap().value().parse().unwrap();
+        let created = plan.tree.created(is_male);
+        if created.is_empty() {
+            status.set(Some("كل الافراد موجودون".to_string()));
+            return;
+        }
+        spawn_local(async move {
+            let message = match import_lineages(created).await {
+                Ok(Committed::Applied) => {
+                    planned.set(None);
+                    text.set(String::new());
+                    "تمت الاضافة".to_string()
+                }
+                Ok(Committed::Proposed) => {
+                    planned.set(None);
+                    text.set(String::new());
+                    "تم ارسال الاضافة للمراجعة".to_string()
+                }
+                Ok(Committed::Conflicted(found)) => {
+                    let names = found
+                        .into_iter()
+                        .filter_map(|x| x.yours.map(|x| x.name))
+                        .collect::<Vec<_>>();
+                    format!("اضيف افراد بنفس الاسم منذ المعاينة: {}", names.join("، "))
+                }
+                Err(_) => "تعذرت الاضافة".to_string(),
+            };
+            status.set(Some(message));
+        });
+    };
+
+    view! {
+        <section class="grid justify-items-center gap-5 overflow-auto">
+            <h1 class="text-center m-5 text-3xl">اضافة جماعية</h1>
+            <p class="text-xl">
+                {move || match parent.get() {
+                    Some(parent) => format!("الاضافة تحت: {}", parent.name),
+                    None => "اختر الفرد الذي تبدأ منه الانساب".to_string(),
+                }}
+            </p>
+            <MemberSearch placeholder="بحث عن الاب" on_pick=pick/>
+            <textarea
+                on:input=move |ev| {
+                    text.set(event_target_value(&ev));
+                    planned.set(None);
+                }
+                prop:value=move || text.get()
+                placeholder="ابن->اب->جد"
+                rows="15"
+                class="w-full max-w-3xl p-2 text-xl bg-gray-800 border-gray-500 border-2 rounded-lg placeholder:text-center placeholder-gray-400"
+            ></textarea>
+            <select
+                node_ref=select_ref
+                class="bg-gray-800 border-gray-500 hover:border-gray-800 text-center border-2 text-xl rounded-lg"
+            >
+                <option value="true">"اخر كل نسب ذكر"</option>
+                <option value="false">"اخر كل نسب انثى"</option>
+            </select>
+            <button
+                on:click=preview
+                disabled=move || parent.get().is_none()
+                class="p-2 w-32 border-2 border-gray-500 bg-gray-800 hover:border-gray-800 rounded-lg"
+            >
+                معاينة
+            </button>
+            <p>{move || status.get()}</p>
+            {move || planned.get().map(|plan| view! { <PlanPreview plan/> })}
+            <Show when=move || planned.get().is_some_and(|x| x.errors.is_empty())>
+                <button
+                    on:click=commit
+                    class="p-2 w-32 border-2 border-green-600 bg-green-950 hover:border-green-950 rounded-lg"
+                >
+                    اضافة
+                </button>
+            </Show>
+        </section>
+    }
+}
+
+#[component]
+fn PlanPreview(plan: Plan) -> impl IntoView {
+    let Plan { tree, errors } = plan;
+    let (new, existing) = tree.count();
+
+    view! {
+        <div class="grid gap-2 p-5 w-full max-w-3xl rounded-lg bg-gray-700 border-2 border-gray-500">
+            <p>
+                <span class="text-green-300">{new}" جديد"</span>
+                " - "
+                {existing}" موجود"
+            </p>
+            {(!errors.is_empty())
+                .then(|| {
+                    view! {
+                        <p class="text-red-300">"لن يضاف شيء حتى تصحح الاسطر التالية:"</p>
+                        <ul class="text-red-300">
+                            {errors.into_iter().map(|x| view! { <li>{x}</li> }).collect_view()}
+                        </ul>
+                    }
+                })}
+            <ul class="grid gap-1">
+                <PlannedMember member=tree/>
+            </ul>
+        </div>
+    }
+}
+
+#[component]
+fn PlannedMember(member: Planned) -> impl IntoView {
+    view! {
+        <li>
+            <span class=match (member.too_long(), member.existing) {
+                (true, _) => "text-red-300",
+                (false, true) => "text-gray-300",
+                (false, false) => "text-green-300",
+            }>
+                {member.name}
+            </span>
+            {(!member.sons.is_empty())
+                .then(|| {
+                    view! {
+                        <ul class="ps-5 border-s-2 border-gray-500">
+                            {member
+                                .sons
+                                .into_iter()
+                                .map(|son| view! { <PlannedMember member=son/> })
+                                .collect_view()}
+                        </ul>
+                    }
+                })}
+        </li>
+    }
+}
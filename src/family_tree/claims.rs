@@ -0,0 +1,154 @@
+use super::nasab::Lineage;
+use crate::db::member::SonlessRawMember;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ClaimView {
+    id: Uuid,
+    username: String,
+    chain: Vec<SonlessRawMember>,
+}
+
+#[server(encoding = "Cbor")]
+pub async fn claim_member(member_id: Uuid) -> Result<(), ServerFnError> {
+    use crate::db::{claims, Pool, Postgres};
+    let user = crate::auth::require_user().await?;
+    let pool = expect_context::<Pool<Postgres>>();
+    let Ok(_) = claims::create(&pool, user.id, member_id).await else {
+        return Err(ServerFnError::ServerError(
+            "error creating claim".to_string(),
+        ));
+    };
+    Ok(())
+}
+
+#[server(encoding = "Cbor")]
+async fn get_claims() -> Result<Vec<ClaimView>, ServerFnError> {
+    use crate::db::{claims, member, Pool, Postgres};
+    let user = crate::auth::require_user().await?;
+    let pool = expect_context::<Pool<Postgres>>();
+    let Ok(pending) = claims::pending(&pool).await else {
+        return Err(ServerFnError::ServerError(
+            "error reading claims".to_string(),
+        ));
+    };
+    let ids = pending.iter().map(|x| x.member_id).collect();
+    let editable = crate::auth::editable(&user, ids).await?;
+    let ids = editable.iter().copied().collect::<Vec<_>>();
+    let Ok(live) = member::read_sonless(&pool, &ids).await else {
+        return Err(ServerFnError::ServerError(
+            "error reading members".to_string(),
+        ));
+    };
+    let mut views = Vec::new();
+    for claim in pending {
+        if user.role == crate::db::users::Role::Editor || !editable.contains(&claim.member_id) {
+            continue;
+        }
+        // a claimed member trashed since has no nasab, the claim can only be rejected
+        let chain = match live.iter().any(|x| x.id == claim.member_id) {
+            true => {
+                let Ok(chain) = member::ancestors(&pool, claim.member_id).await else {
+                    return Err(ServerFnError::ServerError(
+                        "error reading nasab".to_string(),
+                    ));
+                };
+                chain
+            }
+            false => Vec::new(),
+        };
+        views.push(ClaimView {
+            id: claim.id,
+            username: claim.username,
+            chain,
+        });
+    }
+    Ok(views)
+}
+
+#[server(encoding = "Cbor")]
+async fn review_claim(id: Uuid, approve: bool) -> Result<(), ServerFnError> {
+    use crate::db::{claims, Pool, Postgres};
+    let pool = expect_context::<Pool<Postgres>>();
+    let Ok(claim) = claims::read(&pool, id).await else {
+        return Err(ServerFnError::ServerError(
+            "error reading claim".to_string(),
+        ));
+    };
+    let user = crate::auth::require_branch_admin(claim.member_id).await?;
+    let mut transaction = pool.begin().await?;
+    let Ok(_) = claims::review(&mut transaction, &claim, approve, user.id).await else {
+        return Err(ServerFnError::ServerError(
+            "error reviewing claim".to_string(),
+        ));
+    };
+    transaction.commit().await?;
+    Ok(())
+}
+
+#[component]
+pub fn Claims() -> impl IntoView {
+    let claims = Resource::once(get_claims);
+    let review = move |id: Uuid, approve: bool| {
+        spawn_local(async move {
+            if review_claim(id, approve).await.is_ok() {
+                claims.refetch();
+            }
+        });
+    };
+
+    view! {
+        <section class="grid justify-items-center gap-5 overflow-auto">
+            <h1 class="text-center m-5 text-3xl">طلبات ربط الحسابات</h1>
+            <Transition>
+                {move || {
+                    claims
+                        .get()
+                        .map(|claims| match claims {
+                            Ok(claims) if claims.is_empty() => {
+                                view! { <p>"لا توجد طلبات"</p> }.into_view()
+                            }
+                            Ok(claims) => {
+                                claims
+                                    .into_iter()
+                                    .map(|ClaimView { id, username, chain }| {
+                                        let missing = chain.is_empty();
+                                        view! {
+                                            <div class="grid gap-2 p-5 w-full max-w-3xl rounded-lg bg-gray-700 border-2 border-gray-500">
+                                                <p>{username}" يقول انه:"</p>
+                                                <p class="text-xl">
+                                                    {match missing {
+                                                        true => "فرد لم يعد موجودا".into_view(),
+                                                        false => view! { <Lineage chain/> }.into_view(),
+                                                    }}
+                                                </p>
+                                                <div class="flex flex-row gap-3">
+                                                    <Show when=move || !missing>
+                                                        <button
+                                                            on:click=move |_| review(id, true)
+                                                            class="p-2 w-32 border-2 border-green-600 bg-green-950 hover:border-green-950 rounded-lg"
+                                                        >
+                                                            تاكيد
+                                                        </button>
+                                                    </Show>
+                                                    <button
+                                                        on:click=move |_| review(id, false)
+                                                        class="p-2 w-32 border-2 border-red-600 bg-red-950 hover:border-red-950 rounded-lg"
+                                                    >
+                                                        رفض
+                                                    </button>
+                                                </div>
+                                            </div>
+                                        }
+                                    })
+                                    .collect_view()
+                            }
+                            Err(_) => view! { <p>"يجب تسجيل الدخول"</p> }.into_view(),
+                        })
+                }}
+            </Transition>
+        </section>
+    }
+}
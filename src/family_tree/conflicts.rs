@@ -0,0 +1,114 @@
+use super::member::Updates;
+use crate::calendar::Calendar;
+use crate::db::member::{Conflict, SonlessRawMember};
+use leptos::*;
+
+#[component]
+pub fn Conflicts(conflicts: RwSignal<Vec<Conflict>>) -> impl IntoView {
+    view! {
+        <Show when=move || !conflicts.get().is_empty()>
+            <div class="grid gap-3 m-5 w-full max-w-3xl">
+                <p class="text-center text-yellow-300">
+                    "عدل غيرك هذه الافراد بعد تحميلك للشجرة، اختر ما تريد الاحتفاظ به ثم احفظ مجددا"
+                </p>
+                <For
+                    each=move || conflicts.get()
+                    key=|x| x.id
+                    let:conflict
+                >
+                    <ConflictCard conflict conflicts/>
+                </For>
+            </div>
+        </Show>
+    }
+}
+
+#[component]
+fn ConflictCard(conflict: Conflict, conflicts: RwSignal<Vec<Conflict>>) -> impl IntoView {
+    let updates = expect_context::<RwSignal<Updates>>();
+    let id = conflict.id;
+    let resolved = move || conflicts.update(|xs| xs.retain(|x| x.id != id));
+    let same_member = conflict.theirs.as_ref().is_some_and(|x| x.id == id);
+    let theirs_label = if conflict.theirs.is_some() && !same_member {
+        "فرد اخر بنفس الاسم"
+    } else {
+        "النسخة المحفوظة"
+    };
+    let yours = conflict
+        .yours
+        .clone()
+        .map(|x| view! { <Version member=x/> }.into_view())
+        .unwrap_or_else(|| view! { <p>"حذف"</p> }.into_view());
+    let theirs = conflict
+        .theirs
+        .clone()
+        .map(|x| view! { <Version member=x/> }.into_view())
+        .unwrap_or_else(|| view! { <p>"محذوف"</p> }.into_view());
+    let keep = {
+        let conflict = conflict.clone();
+        move |_| {
+            updates.get_untracked().keep_yours(&conflict);
+            resolved();
+        }
+    };
+    let take = move |_| {
+        updates.get_untracked().take_theirs(&conflict);
+        resolved();
+    };
+
+    view! {
+        <div class="grid gap-2 p-5 rounded-lg bg-gray-700 border-2 border-yellow-500">
+            <div class="grid grid-cols-2 gap-5">
+                <div>
+                    <p class="text-gray-300">تعديلك</p>
+                    {yours}
+                </div>
+                <div>
+                    <p class="text-gray-300">{theirs_label}</p>
+                    {theirs}
+                </div>
+            </div>
+            <div class="flex flex-row gap-3">
+                {same_member
+                    .then(|| {
+                        view! {
+                            <button
+                                on:click=keep
+                                class="p-2 border-2 border-green-600 bg-green-950 hover:border-green-950 rounded-lg"
+                            >
+                                الاحتفاظ بتعديلي
+                            </button>
+                        }
+                    })}
+                <button
+                    on:click=take
+                    class="p-2 border-2 border-red-600 bg-red-950 hover:border-red-950 rounded-lg"
+                >
+                    التراجع عن تعديلي
+                </button>
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn Version(member: SonlessRawMember) -> impl IntoView {
+    let names = [member.names.kunya, member.names.laqab, member.names.nisba]
+        .into_iter()
+        .flatten()
+        .chain(member.names.alternate)
+        .collect::<Vec<_>>()
+        .join("، ");
+    let dates = [("الميلاد", member.birth), ("الوفاة", member.death)]
+        .into_iter()
+        .filter_map(|(label, date)| date.map(|x| (label, x.display(Calendar::Gregorian))))
+        .map(|(label, date)| view! { <p>{label}": "{date}</p> })
+        .collect_view();
+
+    view! {
+        <p class="text-xl">{member.name}</p>
+        <p>{if member.is_male { "ذكر" } else { "انثى" }}</p>
+        {(!names.is_empty()).then(|| view! { <p>{names}</p> })}
+        {dates}
+    }
+}
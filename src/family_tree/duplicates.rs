@@ -0,0 +1,268 @@
+use super::nasab::Lineage;
+use crate::calendar::Calendar;
+use crate::db::member::SonlessRawMember;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+const DUPLICATES_LIMIT: i64 = 100;
+const FIELDS: [&str; 7] = [
+    "الاسم",
+    "الام",
+    "الميلاد",
+    "الوفاة",
+    "الكنية",
+    "اللقب",
+    "النسبة",
+];
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DuplicateView {
+    first: Vec<SonlessRawMember>,
+    second: Vec<SonlessRawMember>,
+    mothers: (Option<String>, Option<String>),
+}
+
+#[server(encoding = "Cbor")]
+async fn get_duplicates() -> Result<Vec<DuplicateView>, ServerFnError> {
+    use crate::db::{member, Pool, Postgres};
+    use std::collections::HashMap;
+    let user = crate::auth::require_user().await?;
+    let pool = expect_context::<Pool<Postgres>>();
+    let Ok(pairs) = member::duplicates(&pool, DUPLICATES_LIMIT).await else {
+        return Err(ServerFnError::ServerError(
+            "error reading duplicates".to_string(),
+        ));
+    };
+    let ids = pairs.iter().flat_map(|(a, b)| [*a, *b]).collect();
+    let editable = crate::auth::editable(&user, ids).await?;
+    let mut views = Vec::new();
+    for (first, second) in pairs {
+        if !editable.contains(&first) || !editable.contains(&second) {
+            continue;
+        }
+        let (Ok(first), Ok(second)) = (
+            member::ancestors(&pool, first).await,
+            member::ancestors(&pool, second).await,
+        ) else {
+            return Err(ServerFnError::ServerError(
+                "error reading member".to_string(),
+            ));
+        };
+        let mother_ids = [first.first(), second.first()]
+            .into_iter()
+            .flatten()
+            .filter_map(|x| x.mother_id)
+            .collect::<Vec<_>>();
+        let Ok(mothers) = member::read_sonless(&pool, &mother_ids).await else {
+            return Err(ServerFnError::ServerError(
+                "error reading member".to_string(),
+            ));
+        };
+        let mothers = mothers
+            .into_iter()
+            .map(|x| (x.id, x.name))
+            .collect::<HashMap<_, _>>();
+        let mother = |chain: &[SonlessRawMember]| {
+            chain
+                .first()
+                .and_then(|x| x.mother_id)
+                .and_then(|x| mothers.get(&x).cloned())
+        };
+        views.push(DuplicateView {
+            mothers: (mother(&first), mother(&second)),
+            first,
+            second,
+        });
+    }
+    Ok(views)
+}
+
+#[server(encoding = "Cbor")]
+async fn merge_members(
+    merged: SonlessRawMember,
+    loser: SonlessRawMember,
+) -> Result<(), ServerFnError> {
+    use crate::db::{history::Batch, member, Pool, Postgres};
+    let user = crate::auth::require_user().await?;
+    let editable = crate::auth::editable(&user, vec![merged.id, loser.id]).await?;
+    if !editable.contains(&merged.id) || !editable.contains(&loser.id) {
+        return Err(ServerFnError::ServerError("permission denied".to_string()));
+    }
+    let pool = expect_context::<Pool<Postgres>>();
+    let mut transaction = pool.begin().await?;
+    let batch = Batch::new(user.id);
+    let Ok(_) = member::merge(&mut transaction, merged, loser.id, loser.version, &batch).await
+    else {
+        return Err(ServerFnError::ServerError(
+            "error merging members".to_string(),
+        ));
+    };
+    transaction.commit().await?;
+    Ok(())
+}
+
+fn display(member: &SonlessRawMember, mother: &Option<String>, field: usize) -> Option<String> {
+    let date = |x: Option<crate::calendar::MemberDate>| x.map(|x| x.display(Calendar::Gregorian));
+    match field {
+        0 => Some(member.name.clone()),
+        1 => mother.clone(),
+        2 => date(member.birth),
+        3 => date(member.death),
+        4 => member.names.kunya.clone(),
+        5 => member.names.laqab.clone(),
+        _ => member.names.nisba.clone(),
+    }
+}
+
+fn take(merged: &mut SonlessRawMember, other: &SonlessRawMember, field: usize) {
+    match field {
+        0 => merged.name = other.name.clone(),
+        1 => merged.mother_id = other.mother_id,
+        2 => merged.birth = other.birth,
+        3 => merged.death = other.death,
+        4 => merged.names.kunya = other.names.kunya.clone(),
+        5 => merged.names.laqab = other.names.laqab.clone(),
+        _ => merged.names.nisba = other.names.nisba.clone(),
+    }
+}
+
+#[component]
+pub fn Duplicates() -> impl IntoView {
+    let duplicates = Resource::once(get_duplicates);
+    let status = RwSignal::new(None::<&'static str>);
+    let merge = move |merged: SonlessRawMember, loser: SonlessRawMember| {
+        spawn_local(async move {
+            match merge_members(merged, loser).await {
+                Ok(_) => {
+                    status.set(Some("تم الدمج"));
+                    duplicates.refetch();
+                }
+                Err(_) => status.set(Some("تعذر الدمج")),
+            }
+        });
+    };
+
+    view! {
+        <section class="grid justify-items-center gap-5 overflow-auto">
+            <h1 class="text-center m-5 text-3xl">الافراد المكررون</h1>
+            <p>{move || status.get()}</p>
+            <Transition>
+                {move || {
+                    duplicates
+                        .get()
+                        .map(|duplicates| match duplicates {
+                            Ok(duplicates) if duplicates.is_empty() => {
+                                view! { <p>"لا يوجد افراد مكررون"</p> }.into_view()
+                            }
+                            Ok(duplicates) => {
+                                duplicates
+                                    .into_iter()
+                                    .map(|view| view! { <DuplicateCard view merge/> })
+                                    .collect_view()
+                            }
+                            Err(_) => view! { <p>"يجب تسجيل الدخول"</p> }.into_view(),
+                        })
+                }}
+            </Transition>
+        </section>
+    }
+}
+
+#[component]
+fn DuplicateCard<F>(view: DuplicateView, merge: F) -> impl IntoView
+where
+    F: Fn(SonlessRawMember, SonlessRawMember) + Copy + 'static,
+{
+    let DuplicateView {
+        first,
+        second,
+        mothers,
+    } = view;
+    let (Some(a), Some(b)) = (first.first().cloned(), second.first().cloned()) else {
+        return ().into_view();
+    };
+    let keep_second = RwSignal::new(false);
+    let picks = RwSignal::new([false; FIELDS.len()]);
+    let rows = (0..FIELDS.len())
+        .filter_map(|field| {
+            let (x, y) = (
+                display(&a, &mothers.0, field),
+                display(&b, &mothers.1, field),
+            );
+            (x != y).then_some((field, x, y))
+        })
+        .map(|(field, x, y)| {
+            let choice = move |second: bool, value: Option<String>| {
+                view! {
+                    <label class="flex flex-row gap-2">
+                        <input
+                            type="radio"
+                            prop:checked=move || picks.get()[field] == second
+                            on:change=move |_| picks.update(|xs| xs[field] = second)
+                        />
+                        {value.unwrap_or_else(|| "-".to_string())}
+                    </label>
+                }
+            };
+            view! {
+                <p class="text-gray-300">{FIELDS[field]}</p>
+                {choice(false, x)}
+                {choice(true, y)}
+            }
+        })
+        .collect_view();
+    let submit = move |_| {
+        let (survivor, loser) = if keep_second.get_untracked() {
+            (b.clone(), a.clone())
+        } else {
+            (a.clone(), b.clone())
+        };
+        let mut merged = survivor.clone();
+        for (field, second) in picks.get_untracked().into_iter().enumerate() {
+            let other = if second { &b } else { &a };
+            take(&mut merged, other, field);
+        }
+        for name in loser.names.alternate.iter() {
+            if !merged.names.alternate.contains(name) {
+                merged.names.alternate.push(name.clone());
+            }
+        }
+        merge(merged, loser);
+    };
+
+    view! {
+        <div class="grid gap-2 p-5 w-full max-w-3xl rounded-lg bg-gray-700 border-2 border-gray-500">
+            <div class="grid grid-cols-[auto_1fr_1fr] gap-x-5 gap-y-2">
+                <p></p>
+                <p><Lineage chain=first/></p>
+                <p><Lineage chain=second/></p>
+                <p class="text-gray-300">"الاحتفاظ بـ"</p>
+                <label class="flex flex-row gap-2">
+                    <input
+                        type="radio"
+                        prop:checked=move || !keep_second.get()
+                        on:change=move |_| keep_second.set(false)
+                    />
+                    الاول
+                </label>
+                <label class="flex flex-row gap-2">
+                    <input
+                        type="radio"
+                        prop:checked=move || keep_second.get()
+                        on:change=move |_| keep_second.set(true)
+                    />
+                    الثاني
+                </label>
+                {rows}
+            </div>
+            <button
+                on:click=submit
+                class="p-2 w-32 border-2 border-yellow-600 bg-yellow-950 hover:border-yellow-950 rounded-lg"
+            >
+                دمج
+            </button>
+        </div>
+    }
+    .into_view()
+}
@@ -0,0 +1,849 @@
+use std::collections::{HashMap, HashSet};
+
+use super::import::Imported;
+use crate::calendar::{DatePrecision, MemberDate};
+use crate::db::{
+    marriage::{Marriage, MarriageStatus},
+    member::{NameParts, RawMember, SonlessRawMember, NAME_LENGTH},
+};
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+const MONTHS: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+// bookkeeping tags that carry nothing worth importing
+const IGNORED: [&str; 6] = ["CHAN", "CREA", "UID", "_UID", "RIN", "REFN"];
+
+struct Node {
+    line: usize,
+    xref: Option<String>,
+    tag: String,
+    value: String,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn child(&self, tag: &str) -> Option<&Node> {
+        self.children.iter().find(|x| x.tag == tag)
+    }
+
+    fn children<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = &'a Node> {
+        self.children.iter().filter(move |x| x.tag == tag)
+    }
+
+    fn pointer(&self) -> Option<&str> {
+        let value = self.value.trim();
+        (value.len() > 2 && value.starts_with('@') && value.ends_with('@'))
+            .then(|| &value[1..value.len() - 1])
+    }
+
+    fn label(&self) -> String {
+        match &self.xref {
+            Some(xref) => format!("{} @{xref}@", self.tag),
+            None => format!("{} (سطر {})", self.tag, self.line),
+        }
+    }
+}
+
+fn nodes(text: &str) -> anyhow::Result<Vec<Node>> {
+    fn close(stack: &mut Vec<Node>, roots: &mut Vec<Node>, level: usize) {
+        while stack.len() > level {
+            let Some(node) = stack.pop() else {
+                return;
+            };
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(node),
+                None => roots.push(node),
+            }
+        }
+    }
+    let mut stack: Vec<Node> = Vec::new();
+    let mut roots = Vec::new();
+    for (index, line) in text.trim_start_matches('\u{feff}').lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim_start();
+        if line.is_empty() {
+            continue;
+        }
+        let (level, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let Ok(level) = level.parse::<usize>() else {
+            anyhow::bail!("line {line_number}: invalid level");
+        };
+        if level > stack.len() {
+            anyhow::bail!("line {line_number}: level {level} has no parent");
+        }
+        let (xref, rest) = match rest.strip_prefix('@') {
+            Some(rest) => {
+                let (xref, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+                (Some(xref.trim_end_matches('@').to_string()), rest)
+            }
+            None => (None, rest),
+        };
+        let (tag, value) = rest.split_once(' ').unwrap_or((rest, ""));
+        if tag.is_empty() {
+            anyhow::bail!("line {line_number}: missing tag");
+        }
+        close(&mut stack, &mut roots, level);
+        if tag == "CONT" || tag == "CONC" {
+            let Some(parent) = stack.last_mut() else {
+                anyhow::bail!("line {line_number}: {tag} without a parent");
+            };
+            if tag == "CONT" {
+                parent.value.push('\n');
+            }
+            parent.value.push_str(value);
+            continue;
+        }
+        stack.push(Node {
+            line: line_number,
+            xref,
+            tag: tag.to_string(),
+            value: value.to_string(),
+            children: Vec::new(),
+        });
+    }
+    close(&mut stack, &mut roots, 0);
+    Ok(roots)
+}
+
+fn date(value: &str) -> Result<MemberDate, String> {
+    let value = value.split('(').next().unwrap_or_default();
+    let mut tokens = value
+        .split_whitespace()
+        .map(|x| x.to_uppercase())
+        .collect::<Vec<_>>();
+    if let Some(calendar) = tokens.first().cloned() {
+        let calendar = calendar.trim_start_matches("@#D").trim_end_matches('@');
+        if ["JULIAN", "HEBREW", "FRENCH", "FRENCH_R"].contains(&calendar) {
+            return Err(format!("التقويم {calendar} غير مدعوم"));
+        }
+        if calendar == "GREGORIAN" {
+            tokens.remove(0);
+        }
+    }
+    let mut precision = None;
+    if let Some(qualifier) = tokens.first() {
+        precision = match qualifier.as_str() {
+            "ABT" | "CAL" | "EST" | "BET" => Some(DatePrecision::Approximate),
+            "BEF" | "TO" => Some(DatePrecision::Before),
+            "AFT" | "FROM" => Some(DatePrecision::After),
+            "INT" => Some(DatePrecision::Exact),
+            _ => None,
+        };
+        if precision.is_some() {
+            tokens.remove(0);
+        }
+    }
+    let tokens = tokens
+        .into_iter()
+        .take_while(|x| x != "AND" && x != "TO")
+        .collect::<Vec<_>>();
+    if tokens.iter().any(|x| x.contains("B.C") || x == "BCE") {
+        return Err("التواريخ قبل الميلاد غير مدعومة".to_string());
+    }
+    let year = |x: &str| x.split('/').next().and_then(|x| x.parse::<i32>().ok());
+    let month = |x: &str| MONTHS.iter().position(|m| *m == x).map(|x| x as u32 + 1);
+    let parsed = match tokens.as_slice() {
+        [day, m, y] => day.parse::<u32>().ok().and_then(|day| {
+            NaiveDate::from_ymd_opt(year(y)?, month(m)?, day)
+                .map(|date| (date, DatePrecision::Exact))
+        }),
+        [m, y] => month(m).and_then(|m| {
+            NaiveDate::from_ymd_opt(year(y)?, m, 1).map(|date| (date, DatePrecision::Year))
+        }),
+        [y] => year(y)
+            .and_then(|y| NaiveDate::from_ymd_opt(y, 1, 1))
+            .map(|date| (date, DatePrecision::Year)),
+        _ => None,
+    };
+    let Some((date, exact)) = parsed else {
+        return Err(format!("تاريخ غير مفهوم: {}", value.trim()));
+    };
+    Ok(MemberDate {
+        date,
+        precision: precision
+            .filter(|x| *x != DatePrecision::Exact || exact == DatePrecision::Exact)
+            .unwrap_or(exact),
+    })
+}
+
+// siblings can not share a name, so later namesakes get a number
+fn unique_name(member: &SonlessRawMember, names: &mut HashSet<String>) -> SonlessRawMember {
+    let mut renamed = member.clone();
+    let mut n = 2;
+    while !names.insert(renamed.name.clone()) {
+        renamed.name = format!("{} ({n})", member.name);
+        n += 1;
+    }
+    renamed
+}
+
+struct Person {
+    member: SonlessRawMember,
+    father: Option<Uuid>,
+}
+
+fn person(node: &Node, unmapped: &mut Vec<String>) -> Option<SonlessRawMember> {
+    let label = node.label();
+    let names = node
+        .children("NAME")
+        .filter_map(|name| {
+            let given = name
+                .child("GIVN")
+                .map(|x| x.value.trim().to_string())
+                .unwrap_or_else(|| {
+                    name.value
+                        .split('/')
+                        .next()
+                        .unwrap_or_default()
+                        .trim()
+                        .to_string()
+                });
+            let surname = name.value.split('/').nth(1).unwrap_or_default().trim();
+            let given = if given.is_empty() {
+                surname.to_string()
+            } else {
+                given
+            };
+            (!given.is_empty()).then_some((given, name))
+        })
+        .collect::<Vec<_>>();
+    let Some((given, first)) = names.first() else {
+        unmapped.push(format!("{label}: بدون اسم"));
+        return None;
+    };
+    let name = given.clone();
+    if name.chars().count() > NAME_LENGTH {
+        unmapped.push(format!(
+            "{label} {name}: الاسم اطول من {NAME_LENGTH} حرفا، لم يستورد"
+        ));
+        return None;
+    }
+    let is_male = match node.child("SEX").map(|x| x.value.trim()) {
+        Some("M") => true,
+        Some("F") => false,
+        _ => {
+            unmapped.push(format!("{label} {name}: الجنس غير معروف، اعتبر ذكرا"));
+            true
+        }
+    };
+    let mut event = |tag: &str| {
+        let value = node.child(tag)?.child("DATE")?.value.clone();
+        match date(&value) {
+            Ok(date) => Some(date),
+            Err(error) => {
+                unmapped.push(format!("{label} {name}: {error}"));
+                None
+            }
+        }
+    };
+    let birth = event("BIRT");
+    let death = event("DEAT");
+    let part = |tag: &str| first.child(tag).map(|x| x.value.trim().to_string());
+    let skipped = node
+        .children
+        .iter()
+        .map(|x| x.tag.as_str())
+        .filter(|x| !["NAME", "SEX", "BIRT", "DEAT", "FAMC", "FAMS"].contains(x))
+        .filter(|x| !IGNORED.contains(x))
+        .collect::<Vec<_>>();
+    if !skipped.is_empty() {
+        unmapped.push(format!("{label} {name}: لم تستورد {}", skipped.join("، ")));
+    }
+    Some(SonlessRawMember {
+        id: Uuid::new_v4(),
+        name,
+        is_male,
+        mother_id: None,
+        birth,
+        death,
+        names: NameParts {
+            kunya: part("NPFX"),
+            laqab: part("NICK"),
+            nisba: part("NSFX"),
+            alternate: names.iter().skip(1).map(|(x, _)| x.clone()).collect(),
+        },
+        version: 0,
+    })
+}
+
+pub fn parse(text: &str) -> anyhow::Result<Imported> {
+    let records = nodes(text)?;
+    let mut unmapped = Vec::new();
+    let mut people = HashMap::new();
+    let mut order = Vec::new();
+    for record in records.iter() {
+        match record.tag.as_str() {
+            "HEAD" | "TRLR" | "SUBM" => {}
+            "INDI" => {
+                let Some(xref) = record.xref.clone() else {
+                    unmapped.push(format!("{}: بدون معرف", record.label()));
+                    continue;
+                };
+                if let Some(member) = person(record, &mut unmapped) {
+                    order.push(xref.clone());
+                    people.insert(
+                        xref,
+                        Person {
+                            member,
+                            father: None,
+                        },
+                    );
+                }
+            }
+            "FAM" => {}
+            _ => unmapped.push(format!("{}: لم يستورد", record.label())),
+        }
+    }
+
+    let mut marriages = Vec::new();
+    let mut placed = HashSet::new();
+    for family in records.iter().filter(|x| x.tag == "FAM") {
+        let label = family.label();
+        let spouse = |tag: &str| {
+            family
+                .child(tag)
+                .and_then(Node::pointer)
+                .and_then(|x| people.get(x))
+                .map(|x| x.member.clone())
+        };
+        let (husband, wife) = (spouse("HUSB"), spouse("WIFE"));
+        let father = husband.as_ref().filter(|x| x.is_male).map(|x| x.id);
+        let mother = wife.as_ref().filter(|x| !x.is_male).map(|x| x.id);
+        if husband.as_ref().is_some_and(|x| !x.is_male) || wife.as_ref().is_some_and(|x| x.is_male)
+        {
+            unmapped.push(format!("{label}: جنس الزوجين لا يطابق دورهما"));
+        }
+        for child in family.children("CHIL").filter_map(Node::pointer) {
+            let Some(person) = people.get_mut(child) else {
+                continue;
+            };
+            if !placed.insert(child.to_string()) {
+                unmapped.push(format!(
+                    "{label}: {} مسجل في اكثر من عائلة، ابقيت الاولى",
+                    person.member.name
+                ));
+                continue;
+            }
+            person.father = father;
+            person.member.mother_id = mother;
+        }
+        let (Some(husband), Some(wife)) = (husband, wife) else {
+            continue;
+        };
+        if !husband.is_male || wife.is_male {
+            continue;
+        }
+        let event_date = |tag: &str| {
+            family
+                .child(tag)
+                .and_then(|x| x.child("DATE"))
+                .and_then(|x| date(&x.value).ok())
+                .map(|x| x.date)
+        };
+        marriages.push(Marriage {
+            id: Uuid::new_v4(),
+            husband,
+            wife,
+            start_date: event_date("MARR"),
+            end_date: event_date("DIV"),
+            status: if family.child("DIV").is_some() {
+                MarriageStatus::Divorced
+            } else {
+                MarriageStatus::Married
+            },
+        });
+        let skipped = family
+            .children
+            .iter()
+            .map(|x| x.tag.as_str())
+            .filter(|x| !["HUSB", "WIFE", "CHIL", "MARR", "DIV", "NCHI"].contains(x))
+            .filter(|x| !IGNORED.contains(x))
+            .collect::<Vec<_>>();
+        if !skipped.is_empty() {
+            unmapped.push(format!("{label}: لم تستورد {}", skipped.join("، ")));
+        }
+    }
+
+    let people = order
+        .iter()
+        .filter_map(|x| people.remove(x))
+        .collect::<Vec<_>>();
+    let mut sons = HashMap::<Uuid, Vec<SonlessRawMember>>::new();
+    for person in people.iter() {
+        if let Some(father) = person.father {
+            sons.entry(father).or_default().push(person.member.clone());
+        }
+    }
+    fn build(
+        member: SonlessRawMember,
+        sons: &HashMap<Uuid, Vec<SonlessRawMember>>,
+        built: &mut HashSet<Uuid>,
+        unmapped: &mut Vec<String>,
+    ) -> RawMember {
+        built.insert(member.id);
+        let mut names = HashSet::new();
+        let sons = sons
+            .get(&member.id)
+            .into_iter()
+            .flatten()
+            .map(|son| {
+                let renamed = unique_name(son, &mut names);
+                if renamed.name != son.name {
+                    unmapped.push(format!(
+                        "{} بن {}: اسم مكرر بين الاخوة، اعيدت تسميته الى {}",
+                        son.name, member.name, renamed.name
+                    ));
+                }
+                build(renamed, sons, built, unmapped)
+            })
+            .collect();
+        member.with_sons(sons, false)
+    }
+    let mut built = HashSet::new();
+    let mut names = HashSet::new();
+    let mut roots = Vec::new();
+    for root in people.iter().filter(|x| x.father.is_none()) {
+        let member = unique_name(&root.member, &mut names);
+        roots.push(build(member, &sons, &mut built, &mut unmapped));
+    }
+    for person in people.iter().filter(|x| !built.contains(&x.member.id)) {
+        unmapped.push(format!(
+            "{}: ضمن حلقة في النسب، لم يستورد",
+            person.member.name
+        ));
+    }
+    fn clear_mothers(member: &mut RawMember, built: &HashSet<Uuid>) {
+        if member.mother_id.is_some_and(|x| !built.contains(&x)) {
+            member.mother_id = None;
+        }
+        for son in member.sons.iter_mut() {
+            clear_mothers(son, built);
+        }
+    }
+    for root in roots.iter_mut() {
+        clear_mothers(root, &built);
+    }
+    marriages.retain(|x| built.contains(&x.husband.id) && built.contains(&x.wife.id));
+    Ok(Imported {
+        roots,
+        marriages,
+        unmapped,
+    })
+}
+
+#[cfg(feature = "ssr")]
+fn format_date(date: &MemberDate) -> String {
+    use chrono::Datelike;
+    let year = date.date.year();
+    match date.precision {
+        DatePrecision::Exact => format!(
+            "{} {} {year}",
+            date.date.day(),
+            MONTHS[date.date.month0() as usize]
+        ),
+        DatePrecision::Year => year.to_string(),
+        DatePrecision::Approximate => format!("ABT {year}"),
+        DatePrecision::Before => format!("BEF {year}"),
+        DatePrecision::After => format!("AFT {year}"),
+    }
+}
+
+// the subtree as a gedcom 5.5.1 file, with the spouses and mothers from outside
+// the subtree as individuals without parents
+#[cfg(feature = "ssr")]
+fn write(root: &RawMember, marriages: &[Marriage], outsiders: &[SonlessRawMember]) -> String {
+    struct Family<'a> {
+        husband: Option<Uuid>,
+        wife: Option<Uuid>,
+        marriage: Option<&'a Marriage>,
+        children: Vec<Uuid>,
+    }
+    fn flatten(
+        member: &RawMember,
+        father: Option<Uuid>,
+        out: &mut Vec<(SonlessRawMember, Option<Uuid>)>,
+    ) {
+        let sonless = SonlessRawMember {
+            id: member.id,
+            name: member.name.clone(),
+            is_male: member.is_male,
+            mother_id: member.mother_id,
+            birth: member.birth,
+            death: member.death,
+            names: member.names.clone(),
+            version: member.version,
+        };
+        out.push((sonless, father));
+        for son in member.sons.iter() {
+            flatten(son, Some(member.id), out);
+        }
+    }
+    let mut people = Vec::new();
+    flatten(root, None, &mut people);
+    for outsider in outsiders {
+        if people.iter().all(|(x, _)| x.id != outsider.id) {
+            people.push((outsider.clone(), None));
+        }
+    }
+    let individuals = people
+        .iter()
+        .enumerate()
+        .map(|(index, (x, _))| (x.id, format!("@I{}@", index + 1)))
+        .collect::<HashMap<_, _>>();
+
+    let mut families: Vec<Family> = Vec::new();
+    for marriage in marriages {
+        let known = individuals.contains_key(&marriage.husband.id)
+            && individuals.contains_key(&marriage.wife.id);
+        if known
+            && families
+                .iter()
+                .all(|x| x.marriage.map(|x| x.id) != Some(marriage.id))
+        {
+            families.push(Family {
+                husband: Some(marriage.husband.id),
+                wife: Some(marriage.wife.id),
+                marriage: Some(marriage),
+                children: Vec::new(),
+            });
+        }
+    }
+    for (member, father) in people.iter() {
+        let Some(father) = *father else {
+            continue;
+        };
+        let mother = member.mother_id.filter(|x| individuals.contains_key(x));
+        let index = match families
+            .iter()
+            .position(|x| x.husband == Some(father) && (mother.is_none() || x.wife == mother))
+        {
+            Some(index) => index,
+            None => {
+                families.push(Family {
+                    husband: Some(father),
+                    wife: mother,
+                    marriage: None,
+                    children: Vec::new(),
+                });
+                families.len() - 1
+            }
+        };
+        families[index].children.push(member.id);
+    }
+    let family_xref = |index: usize| format!("@F{}@", index + 1);
+    let mut child_of = HashMap::new();
+    let mut spouse_in = HashMap::<Uuid, Vec<String>>::new();
+    for (index, family) in families.iter().enumerate() {
+        for child in family.children.iter() {
+            child_of.entry(*child).or_insert_with(|| family_xref(index));
+        }
+        for spouse in [family.husband, family.wife].into_iter().flatten() {
+            spouse_in
+                .entry(spouse)
+                .or_default()
+                .push(family_xref(index));
+        }
+    }
+
+    let mut lines = vec![
+        "0 HEAD".to_string(),
+        "1 SOUR ENSAB".to_string(),
+        "1 GEDC".to_string(),
+        "2 VERS 5.5.1".to_string(),
+        "2 FORM LINEAGE-LINKED".to_string(),
+        "1 CHAR UTF-8".to_string(),
+        "1 SUBM @U1@".to_string(),
+        "0 @U1@ SUBM".to_string(),
+        "1 NAME ensab".to_string(),
+    ];
+    for (member, _) in people.iter() {
+        lines.push(format!("0 {} INDI", individuals[&member.id]));
+        lines.push(format!("1 NAME {}", member.name));
+        lines.push(format!("2 GIVN {}", member.name));
+        for (tag, part) in [
+            ("NPFX", &member.names.kunya),
+            ("NICK", &member.names.laqab),
+            ("NSFX", &member.names.nisba),
+        ] {
+            if let Some(part) = part {
+                lines.push(format!("2 {tag} {part}"));
+            }
+        }
+        for alternate in member.names.alternate.iter() {
+            lines.push(format!("1 NAME {alternate}"));
+        }
+        lines.push(format!("1 SEX {}", if member.is_male { "M" } else { "F" }));
+        for (tag, date) in [("BIRT", member.birth), ("DEAT", member.death)] {
+            if let Some(date) = date {
+                lines.push(format!("1 {tag}"));
+                lines.push(format!("2 DATE {}", format_date(&date)));
+            }
+        }
+        if let Some(family) = child_of.get(&member.id) {
+            lines.push(format!("1 FAMC {family}"));
+        }
+        for family in spouse_in.get(&member.id).into_iter().flatten() {
+            lines.push(format!("1 FAMS {family}"));
+        }
+        lines.push(format!("1 REFN {}", member.id));
+    }
+    for (index, family) in families.iter().enumerate() {
+        lines.push(format!("0 {} FAM", family_xref(index)));
+        if let Some(husband) = family.husband {
+            lines.push(format!("1 HUSB {}", individuals[&husband]));
+        }
+        if let Some(wife) = family.wife {
+            lines.push(format!("1 WIFE {}", individuals[&wife]));
+        }
+        for child in family.children.iter() {
+            lines.push(format!("1 CHIL {}", individuals[child]));
+        }
+        let Some(marriage) = family.marriage else {
+            continue;
+        };
+        let exact = |date: chrono::NaiveDate| {
+            format_date(&MemberDate {
+                date,
+                precision: DatePrecision::Exact,
+            })
+        };
+        lines.push("1 MARR Y".to_string());
+        if let Some(date) = marriage.start_date {
+            lines.push(format!("2 DATE {}", exact(date)));
+        }
+        if marriage.status == MarriageStatus::Divorced {
+            lines.push("1 DIV Y".to_string());
+            if let Some(date) = marriage.end_date {
+                lines.push(format!("2 DATE {}", exact(date)));
+            }
+        }
+    }
+    lines.push("0 TRLR".to_string());
+    format!("\u{feff}{}\n", lines.join("\n"))
+}
+
+#[cfg(feature = "ssr")]
+pub async fn export(
+    axum::Extension(pool): axum::Extension<crate::db::Pool<crate::db::Postgres>>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> axum::response::Response {
+    use super::member::Rm;
+    use crate::db::{marriage, member};
+    use axum::{
+        http::{header, StatusCode},
+        response::IntoResponse,
+    };
+    let Ok(root) = member::read(&pool, id, None).await else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let ids = root.ids();
+    let Ok(marriages) = marriage::read(&pool, &ids).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let mothers = {
+        fn mothers(member: &RawMember, out: &mut Vec<Uuid>) {
+            out.extend(member.mother_id);
+            for son in member.sons.iter() {
+                mothers(son, out);
+            }
+        }
+        let mut out = Vec::new();
+        mothers(&root, &mut out);
+        out.retain(|x| !ids.contains(x));
+        out
+    };
+    let Ok(mothers) = member::read_sonless(&pool, &mothers).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let outsiders = marriages
+        .iter()
+        .flat_map(|x| [x.husband.clone(), x.wife.clone()])
+        .chain(mothers)
+        .collect::<Vec<_>>();
+    (
+        [
+            (
+                header::CONTENT_TYPE,
+                "text/plain; charset=utf-8".to_string(),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{id}.ged\""),
+            ),
+        ],
+        write(&root, &marriages, &outsiders),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ymd(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn date_of(value: &str) -> MemberDate {
+        date(value).unwrap()
+    }
+
+    fn names(member: &RawMember) -> Vec<&str> {
+        member.sons.iter().map(|x| x.name.as_str()).collect()
+    }
+
+    #[test]
+    fn continuation_lines_join_their_parent() {
+        let text = "0 NOTE first\n1 CONT second\n1 CONC  half\n0 TRLR";
+        let records = nodes(text).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].value, "first\nsecond half");
+        assert!(nodes("1 CONT orphan").is_err());
+    }
+
+    #[test]
+    fn date_qualifiers() {
+        let date = date_of("ABT 1900");
+        assert_eq!(date.date, ymd(1900, 1, 1));
+        assert_eq!(date.precision, DatePrecision::Approximate);
+        let date = date_of("BET 3 MAR 1850 AND 1860");
+        assert_eq!(date.date, ymd(1850, 3, 3));
+        assert_eq!(date.precision, DatePrecision::Approximate);
+        let date = date_of("FROM JUN 1920 TO 1930");
+        assert_eq!(date.date, ymd(1920, 6, 1));
+        assert_eq!(date.precision, DatePrecision::After);
+        let date = date_of("BEF 12 DEC 1901");
+        assert_eq!(date.date, ymd(1901, 12, 12));
+        assert_eq!(date.precision, DatePrecision::Before);
+        let date = date_of("INT 1900 (about then)");
+        assert_eq!(date.precision, DatePrecision::Year);
+    }
+
+    #[test]
+    fn dual_years_keep_the_first() {
+        let date = date_of("11 FEB 1731/32");
+        assert_eq!(date.date, ymd(1731, 2, 11));
+        assert_eq!(date.precision, DatePrecision::Exact);
+    }
+
+    #[test]
+    fn unsupported_dates_are_errors() {
+        assert!(date("@#DJULIAN@ 1 JAN 1700").is_err());
+        assert!(date("@#DHEBREW@ 5700").is_err());
+        assert!(date("44 B.C.").is_err());
+        assert!(date("sometime").is_err());
+        assert_eq!(date_of("@#DGREGORIAN@ 1 JAN 1700").date, ymd(1700, 1, 1));
+    }
+
+    #[test]
+    fn families_link_fathers_sons_and_mothers() {
+        let text = "\
+0 HEAD
+0 @I1@ INDI
+1 NAME Ahmad /Ali/
+1 SEX M
+1 BIRT
+2 DATE 1 JAN 1900
+0 @I2@ INDI
+1 NAME Fatima
+1 SEX F
+0 @I3@ INDI
+1 NAME Omar
+1 SEX M
+1 FAMC @F1@
+0 @F1@ FAM
+1 HUSB @I1@
+1 WIFE @I2@
+1 CHIL @I3@
+1 MARR
+2 DATE 1920
+0 TRLR";
+        let imported = parse(text).unwrap();
+        assert_eq!(imported.roots.len(), 2);
+        let ahmad = &imported.roots[0];
+        let fatima = &imported.roots[1];
+        assert_eq!(ahmad.name, "Ahmad");
+        assert_eq!(ahmad.birth.as_ref().unwrap().date, ymd(1900, 1, 1));
+        assert_eq!(names(ahmad), ["Omar"]);
+        assert_eq!(ahmad.sons[0].mother_id, Some(fatima.id));
+        assert_eq!(imported.marriages.len(), 1);
+        assert_eq!(imported.marriages[0].husband.id, ahmad.id);
+        assert_eq!(imported.marriages[0].start_date, Some(ymd(1920, 1, 1)));
+        assert!(imported.unmapped.is_empty(), "{:?}", imported.unmapped);
+    }
+
+    #[test]
+    fn namesake_siblings_are_renamed() {
+        let text = "\
+0 @I1@ INDI
+1 NAME Ahmad
+1 SEX M
+0 @I2@ INDI
+1 NAME Ali
+1 SEX M
+0 @I3@ INDI
+1 NAME Ali
+1 SEX M
+0 @F1@ FAM
+1 HUSB @I1@
+1 CHIL @I2@
+1 CHIL @I3@";
+        let imported = parse(text).unwrap();
+        assert_eq!(names(&imported.roots[0]), ["Ali", "Ali (2)"]);
+        assert_eq!(imported.unmapped.len(), 1);
+    }
+
+    #[test]
+    fn cycles_are_left_out() {
+        let text = "\
+0 @I1@ INDI
+1 NAME Ahmad
+1 SEX M
+0 @I2@ INDI
+1 NAME Ali
+1 SEX M
+0 @I3@ INDI
+1 NAME Omar
+1 SEX M
+0 @F1@ FAM
+1 HUSB @I2@
+1 CHIL @I3@
+0 @F2@ FAM
+1 HUSB @I3@
+1 CHIL @I2@";
+        let imported = parse(text).unwrap();
+        assert_eq!(imported.roots.len(), 1);
+        assert_eq!(imported.roots[0].name, "Ahmad");
+        assert_eq!(imported.unmapped.len(), 2);
+        assert!(imported.unmapped.iter().all(|x| x.contains("حلقة")));
+    }
+
+    #[test]
+    fn names_longer_than_the_column_are_not_imported() {
+        let long = "ا".repeat(NAME_LENGTH + 1);
+        let fits = "ب".repeat(NAME_LENGTH);
+        let text =
+            format!("0 @I1@ INDI\n1 NAME {long}\n1 SEX M\n0 @I2@ INDI\n1 NAME {fits}\n1 SEX M");
+        let imported = parse(&text).unwrap();
+        assert_eq!(imported.roots.len(), 1);
+        assert_eq!(imported.roots[0].name, fits);
+        assert_eq!(imported.unmapped.len(), 1);
+        assert!(imported.unmapped[0].starts_with("INDI @I1@"));
+    }
+
+    #[test]
+    fn unsupported_calendars_are_reported() {
+        let text = "\
+0 @I1@ INDI
+1 NAME Ahmad
+1 SEX M
+1 BIRT
+2 DATE @#DJULIAN@ 1 JAN 1700";
+        let imported = parse(text).unwrap();
+        assert_eq!(imported.roots[0].birth, None);
+        assert_eq!(imported.unmapped.len(), 1);
+        assert!(imported.unmapped[0].contains("JULIAN"));
+    }
+}
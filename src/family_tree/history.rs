@@ -0,0 +1,290 @@
+use std::str::FromStr;
+
+use super::{member::Committed, nasab::Lineage};
+use crate::db::{
+    history::{HistoryAction, HistoryEntry, Snapshot},
+    member::SonlessRawMember,
+};
+use leptos::*;
+use leptos_router::use_params_map;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HistoryView {
+    entry: HistoryEntry,
+    before_parent: Option<String>,
+    after_parent: Option<String>,
+}
+
+#[server(encoding = "Cbor")]
+async fn get_history(id: Uuid) -> Result<(Vec<SonlessRawMember>, Vec<HistoryView>), ServerFnError> {
+    use crate::db::{history, member, Pool, Postgres};
+    use std::collections::HashMap;
+    crate::auth::require_user().await?;
+    let pool = expect_context::<Pool<Postgres>>();
+    let chain = member::ancestors(&pool, id).await.unwrap_or_default();
+    let Ok(entries) = history::for_member(&pool, id).await else {
+        return Err(ServerFnError::ServerError(
+            "error reading history".to_string(),
+        ));
+    };
+    let parent_ids = entries
+        .iter()
+        .flat_map(|x| [x.before.as_ref(), x.after.as_ref()])
+        .flatten()
+        .filter_map(|x| x.parent_id)
+        .collect::<Vec<_>>();
+    let Ok(parents) = member::read_sonless(&pool, &parent_ids).await else {
+        return Err(ServerFnError::ServerError(
+            "error reading member".to_string(),
+        ));
+    };
+    let parents = parents
+        .into_iter()
+        .map(|x| (x.id, x.name))
+        .collect::<HashMap<_, _>>();
+    let parent_name = |snapshot: &Option<Snapshot>| {
+        snapshot
+            .as_ref()
+            .and_then(|x| x.parent_id)
+            .and_then(|x| parents.get(&x).cloned())
+    };
+    let views = entries
+        .into_iter()
+        .map(|entry| HistoryView {
+            before_parent: parent_name(&entry.before),
+            after_parent: parent_name(&entry.after),
+            entry,
+        })
+        .collect();
+    Ok((chain, views))
+}
+
+#[server(encoding = "Cbor")]
+async fn restore_version(id: Uuid) -> Result<Committed, ServerFnError> {
+    use super::member::{submit, ServerUpdates};
+    use crate::db::{history, member, member::MovedMember, Pool, Postgres};
+    let user = crate::auth::require_user().await?;
+    let pool = expect_context::<Pool<Postgres>>();
+    let Ok(entry) = history::read(&pool, id).await else {
+        return Err(ServerFnError::ServerError(
+            "error reading history".to_string(),
+        ));
+    };
+    let Some(snapshot) = entry.after else {
+        return Err(ServerFnError::ServerError(
+            "deleted members can only be re-created".to_string(),
+        ));
+    };
+    let Ok(chain) = member::ancestors(&pool, entry.member_id).await else {
+        return Err(ServerFnError::ServerError(
+            "member no longer exists".to_string(),
+        ));
+    };
+    let (current, parent_id) = (&chain[0], chain.get(1).map(|x| x.id));
+    // the version was a move when it names another parent
+    let moved_members = snapshot
+        .parent_id
+        .filter(|x| Some(*x) != parent_id)
+        .map(|x| MovedMember {
+            id: current.id,
+            parent_id: x,
+            version: current.version,
+        })
+        .into_iter()
+        .collect();
+    let updates = ServerUpdates {
+        updated_members: vec![SonlessRawMember {
+            version: current.version,
+            ..snapshot.member
+        }],
+        moved_members,
+        ..Default::default()
+    };
+    submit(&user, updates).await
+}
+
+#[server(encoding = "Cbor")]
+async fn recreate_subtree(id: Uuid) -> Result<Committed, ServerFnError> {
+    use super::member::{submit, ServerUpdates};
+    use crate::db::{history, member, member::RawMember, Pool, Postgres};
+    fn build(snapshot: &Snapshot, removed: &[Snapshot]) -> RawMember {
+        let sons = removed
+            .iter()
+            .filter(|x| x.parent_id == Some(snapshot.member.id))
+            .map(|x| build(x, removed))
+            .collect();
+        snapshot.member.clone().with_sons(sons, false)
+    }
+    let user = crate::auth::require_user().await?;
+    let pool = expect_context::<Pool<Postgres>>();
+    let Ok(entry) = history::read(&pool, id).await else {
+        return Err(ServerFnError::ServerError(
+            "error reading history".to_string(),
+        ));
+    };
+    if entry.action != HistoryAction::Delete {
+        return Err(ServerFnError::ServerError(
+            "history entry is not a deletion".to_string(),
+        ));
+    }
+    if let Ok(Some(deletion_id)) = member::deletion(&pool, entry.member_id).await {
+        super::trash::restore_deletion(&user, deletion_id).await?;
+        return Ok(Committed::Applied);
+    }
+    let Ok(removed) = history::deleted(&pool, entry.batch_id).await else {
+        return Err(ServerFnError::ServerError(
+            "error reading history".to_string(),
+        ));
+    };
+    let Some(root) = removed.iter().find(|x| x.member.id == entry.member_id) else {
+        return Err(ServerFnError::ServerError(
+            "deleted member not found".to_string(),
+        ));
+    };
+    let Some(parent_id) = root.parent_id else {
+        return Err(ServerFnError::ServerError(
+            "deleted member has no parent".to_string(),
+        ));
+    };
+    let updates = ServerUpdates {
+        created_members: vec![(parent_id, build(root, &removed))],
+        ..Default::default()
+    };
+    submit(&user, updates).await
+}
+
+#[component]
+pub fn History() -> impl IntoView {
+    let params = use_params_map();
+    let id = move || {
+        let id = params.with(|x| x.get("id").cloned()).unwrap();
+        Uuid::from_str(&id).unwrap()
+    };
+
+    let history = Resource::new(id, get_history);
+    let status = RwSignal::new(None::<&'static str>);
+    let done = move |result: Result<Committed, ServerFnError>| {
+        match result {
+            Ok(Committed::Applied) => {
+                status.set(Some("تمت الاستعادة"));
+                history.refetch();
+            }
+            Ok(Committed::Proposed) => status.set(Some("تم ارسال الاستعادة للمراجعة")),
+            Ok(Committed::Conflicted(_)) | Err(_) => status.set(Some("تعذرت الاستعادة")),
+        };
+    };
+    let restore = move |id: Uuid, action: HistoryAction| {
+        spawn_local(async move {
+            let result = match action {
+                HistoryAction::Delete => recreate_subtree(id).await,
+                _ => restore_version(id).await,
+            };
+            done(result);
+        });
+    };
+
+    view! {
+        <section class="grid justify-items-center gap-5 overflow-auto">
+            <h1 class="text-center m-5 text-3xl">سجل التعديلات</h1>
+            <p>{move || status.get()}</p>
+            <Transition>
+                {move || {
+                    history
+                        .get()
+                        .map(|history| match history {
+                            Ok((chain, views)) => {
+                                view! {
+                                    <p class="text-center text-2xl">
+                                        <Lineage chain/>
+                                    </p>
+                                    {views.is_empty().then(|| view! { <p>"لا توجد تعديلات"</p> })}
+                                    {views
+                                        .into_iter()
+                                        .map(|view| view! { <HistoryCard view restore/> })
+                                        .collect_view()}
+                                }
+                                    .into_view()
+                            }
+                            Err(_) => view! { <p>"يجب تسجيل الدخول"</p> }.into_view(),
+                        })
+                }}
+            </Transition>
+        </section>
+    }
+}
+
+#[component]
+fn HistoryCard<F>(view: HistoryView, restore: F) -> impl IntoView
+where
+    F: Fn(Uuid, HistoryAction) + Copy + 'static,
+{
+    let HistoryView {
+        entry,
+        before_parent,
+        after_parent,
+    } = view;
+    let HistoryEntry {
+        id,
+        action,
+        author,
+        changed_at,
+        before,
+        after,
+        ..
+    } = entry;
+    let name = |x: &Option<Snapshot>| x.as_ref().map(|x| x.member.name.clone());
+    let gender = |x: &Option<Snapshot>| {
+        x.as_ref().map(|x| {
+            if x.member.is_male {
+                "ذكر"
+            } else {
+                "انثى"
+            }
+            .to_string()
+        })
+    };
+    let changes = [
+        ("الاسم", name(&before), name(&after)),
+        ("الجنس", gender(&before), gender(&after)),
+        ("الاب", before_parent, after_parent),
+    ]
+    .into_iter()
+    .filter(|(_, before, after)| action != HistoryAction::Update || before != after)
+    .map(|(label, before, after)| {
+        let value = match (before, after) {
+            (Some(before), Some(after)) => format!("{before} ← {after}"),
+            (Some(value), None) | (None, Some(value)) => value,
+            (None, None) => "-".to_string(),
+        };
+        view! { <li>{label}": "{value}</li> }
+    })
+    .collect_view();
+    let button = match action {
+        HistoryAction::Delete => Some("اعادة انشاء الفرع"),
+        HistoryAction::Purge | HistoryAction::Merge => None,
+        _ => Some("استعادة هذه النسخة"),
+    }
+    .map(|label| {
+        view! {
+            <button
+                on:click=move |_| restore(id, action)
+                class="p-2 w-48 border-2 border-gray-400 bg-gray-950 hover:border-gray-950 rounded-lg"
+            >
+                {label}
+            </button>
+        }
+    });
+
+    view! {
+        <div class="grid gap-2 p-5 w-full max-w-3xl rounded-lg bg-gray-700 border-2 border-gray-500">
+            <p class="text-gray-300">
+                {action.label()}" - "{author.unwrap_or_default()}" - "
+                {changed_at.format("%Y-%m-%d %H:%M").to_string()}
+            </p>
+            <ul>{changes}</ul>
+            {button}
+        </div>
+    }
+}
@@ -0,0 +1,178 @@
+use super::{gedcom, member::Committed, search::MemberSearch};
+use crate::db::{
+    marriage::Marriage,
+    member::{RawMember, SonlessRawMember},
+};
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Imported {
+    pub roots: Vec<RawMember>,
+    pub marriages: Vec<Marriage>,
+    pub unmapped: Vec<String>,
+}
+
+impl Imported {
+    fn members(&self) -> usize {
+        fn count(member: &RawMember) -> usize {
+            1 + member.sons.iter().map(count).sum::<usize>()
+        }
+        self.roots.iter().map(count).sum()
+    }
+}
+
+#[server(encoding = "Cbor")]
+async fn import_members(
+    parent_id: Uuid,
+    roots: Vec<RawMember>,
+    marriages: Vec<Marriage>,
+) -> Result<Committed, ServerFnError> {
+    use super::member::{submit, ServerUpdates};
+    let user = crate::auth::require_user().await?;
+    let updates = ServerUpdates {
+        created_members: roots.into_iter().map(|x| (parent_id, x)).collect(),
+        created_marriages: marriages,
+        ..Default::default()
+    };
+    submit(&user, updates).await
+}
+
+#[component]
+pub fn Import() -> impl IntoView {
+    let preview = RwSignal::new(None::<Result<Imported, String>>);
+    let parent = RwSignal::new(None::<SonlessRawMember>);
+    let status = RwSignal::new(None::<String>);
+
+    let on_change = move |ev: ev::Event| {
+        let input = event_target::<web_sys::HtmlInputElement>(&ev);
+        let Some(file) = input.files().and_then(|x| x.get(0)) else {
+            return;
+        };
+        status.set(None);
+        spawn_local(async move {
+            let text = wasm_bindgen_futures::JsFuture::from(file.text())
+                .await
+                .ok()
+                .and_then(|x| x.as_string());
+            let parsed = match text {
+                Some(text) => gedcom::parse(&text).map_err(|x| x.to_string()),
+                None => Err("تعذرت قراءة الملف".to_string()),
+            };
+            preview.set(Some(parsed));
+        });
+    };
+    let pick = Callback::new(move |member: SonlessRawMember| parent.set(Some(member)));
+    let commit = move |_| {
+        let (Some(Ok(import)), Some(parent)) = (preview.get_untracked(), parent.get_untracked())
+        else {
+            return;
+        };
+        spawn_local(async move {
+            let message = match import_members(parent.id, import.roots, import.marriages).await {
+                Ok(Committed::Applied) => {
+                    preview.set(None);
+                    "تم الاستيراد".to_string()
+                }
+                Ok(Committed::Proposed) => {
+                    preview.set(None);
+                    "تم ارسال الاستيراد للمراجعة".to_string()
+                }
+                Ok(Committed::Conflicted(found)) => {
+                    let names = found
+                        .into_iter()
+                        .filter_map(|x| x.yours.map(|x| x.name))
+                        .collect::<Vec<_>>();
+                    format!("يوجد افراد بنفس الاسم تحت الاب المختار: {}", names.join("، "))
+                }
+                Err(_) => "تعذر الاستيراد".to_string(),
+            };
+            status.set(Some(message));
+        });
+    };
+
+    view! {
+        <section class="grid justify-items-center gap-5 overflow-auto">
+            <h1 class="text-center m-5 text-3xl">استيراد</h1>
+            <label class="grid justify-items-center gap-2">
+                "ملف GEDCOM"
+                <input type="file" accept=".ged,.gedcom" on:change=on_change class="text-xl"/>
+            </label>
+            <p>{move || status.get()}</p>
+            {move || {
+                preview
+                    .get()
+                    .map(|preview| match preview {
+                        Ok(import) => view! { <ImportPreview import/> }.into_view(),
+                        Err(error) => view! { <p class="text-red-300">{error}</p> }.into_view(),
+                    })
+            }}
+            <Show when=move || matches!(preview.get(), Some(Ok(_)))>
+                <p class="text-xl">
+                    {move || match parent.get() {
+                        Some(parent) => format!("الاضافة تحت: {}", parent.name),
+                        None => "اختر الفرد الذي يضاف تحته المستوردون".to_string(),
+                    }}
+                </p>
+                <MemberSearch placeholder="بحث عن الاب" on_pick=pick/>
+                <button
+                    on:click=commit
+                    disabled=move || parent.get().is_none()
+                    class="p-2 w-32 border-2 border-green-600 bg-green-950 hover:border-green-950 rounded-lg"
+                >
+                    استيراد
+                </button>
+            </Show>
+        </section>
+    }
+}
+
+#[component]
+fn ImportPreview(import: Imported) -> impl IntoView {
+    let members = import.members();
+    let Imported {
+        roots,
+        marriages,
+        unmapped,
+    } = import;
+
+    view! {
+        <div class="grid gap-2 p-5 w-full max-w-3xl rounded-lg bg-gray-700 border-2 border-gray-500">
+            <p>{members}" فرد - "{marriages.len()}" زواج"</p>
+            <ul class="grid gap-1">
+                {roots.into_iter().map(|root| view! { <PreviewMember member=root/> }).collect_view()}
+            </ul>
+            {(!unmapped.is_empty())
+                .then(|| {
+                    view! {
+                        <p class="text-yellow-300">"لم يستورد كما هو:"</p>
+                        <ul class="text-yellow-300">
+                            {unmapped.into_iter().map(|x| view! { <li>{x}</li> }).collect_view()}
+                        </ul>
+                    }
+                })}
+        </div>
+    }
+}
+
+#[component]
+pub fn PreviewMember(member: RawMember) -> impl IntoView {
+    view! {
+        <li>
+            {member.name}
+            {(!member.sons.is_empty())
+                .then(|| {
+                    view! {
+                        <ul class="ps-5 border-s-2 border-gray-500">
+                            {member
+                                .sons
+                                .into_iter()
+                                .map(|son| view! { <PreviewMember member=son/> })
+                                .collect_view()}
+                        </ul>
+                    }
+                })}
+        </li>
+    }
+}
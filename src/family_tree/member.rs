@@ -1,5 +1,11 @@
-use crate::db::member::{RawMember, SonlessRawMember};
-use leptos::{server, RwSignal, ServerFnError, SignalGetUntracked, SignalSet, SignalUpdate};
+use crate::calendar::MemberDate;
+use crate::db::{
+    marriage::Marriage,
+    member::{Conflict, MovedMember, NameParts, RawMember, SonlessRawMember},
+};
+use leptos::{
+    server, RwSignal, ServerFnError, SignalGet, SignalGetUntracked, SignalSet, SignalUpdate,
+};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -10,6 +16,8 @@ pub enum Action {
     Add,
     Remove,
     Update,
+    Marriages,
+    Move,
 }
 
 #[derive(Clone, Copy, Default)]
@@ -17,7 +25,13 @@ pub struct Member {
     pub id: Uuid,
     pub name: RwSignal<String>,
     pub is_male: RwSignal<bool>,
+    pub mother_id: RwSignal<Option<Uuid>>,
+    pub birth: RwSignal<Option<MemberDate>>,
+    pub death: RwSignal<Option<MemberDate>>,
+    pub names: RwSignal<NameParts>,
+    pub version: RwSignal<i32>,
     pub sons: RwSignal<Vec<Member>>,
+    pub unloaded_sons: RwSignal<bool>,
     pub action: RwSignal<Action>,
 }
 
@@ -25,22 +39,160 @@ pub struct Member {
 pub struct Updates {
     origin: RwSignal<RawMember>,
     pub copy: RwSignal<Member>,
+    origin_marriages: RwSignal<Vec<Marriage>>,
+    pub marriages: RwSignal<Vec<Marriage>>,
+    moves: RwSignal<Vec<(Member, Uuid)>>,
+    // members added in this session then moved under a parent outside the loaded tree
+    adopted: RwSignal<Vec<(Member, Uuid)>>,
 }
 
 impl Updates {
-    pub fn init(member: Member) -> Self {
+    pub fn init(member: Member, marriages: Vec<Marriage>) -> Self {
         Self {
             origin: RwSignal::from(member.raw()),
             copy: RwSignal::new(member),
+            origin_marriages: RwSignal::new(marriages.clone()),
+            marriages: RwSignal::new(marriages),
+            moves: RwSignal::new(Vec::new()),
+            adopted: RwSignal::new(Vec::new()),
         }
     }
 
+    // the edited member, even when it was moved out of the loaded tree
+    fn find(&self, id: Uuid) -> Option<Member> {
+        self.copy.get_untracked().find(id).or_else(|| {
+            self.moves
+                .get_untracked()
+                .into_iter()
+                .chain(self.adopted.get_untracked())
+                .find_map(|(member, _)| member.find(id))
+        })
+    }
+
+    // takes the member out of wherever it currently sits
+    fn detach(&self, id: Uuid) {
+        self.copy.get_untracked().remove(id);
+        for moves in [self.moves, self.adopted] {
+            moves.update(|xs| xs.retain(|(x, _)| x.id != id));
+            for (member, _) in moves.get_untracked() {
+                member.remove(id);
+            }
+        }
+    }
+
+    pub fn move_member(&self, member: Member, parent_id: Uuid) -> bool {
+        if member.find(parent_id).is_some() {
+            return false;
+        }
+        self.detach(member.id);
+        let parent = self.find(parent_id);
+        if let Some(parent) = parent {
+            parent.sons.update(|xs| xs.push(member));
+        }
+        // a member that is not stored yet is simply created under its new parent
+        if self.origin.get_untracked().find_son(member.id).is_none() {
+            if parent.is_none() {
+                self.adopted.update(|xs| xs.push((member, parent_id)));
+            }
+            return true;
+        }
+        let returned = self.origin.get_untracked().parent_of(member.id) == Some(parent_id);
+        self.moves.update(|xs| {
+            xs.retain(|(x, _)| x.id != member.id);
+            if !returned {
+                xs.push((member, parent_id));
+            }
+        });
+        true
+    }
+
+    fn unmove(&self, id: Uuid) {
+        let moves = self.moves.get_untracked();
+        let Some((member, _)) = moves.into_iter().find(|(x, _)| x.id == id) else {
+            return;
+        };
+        self.moves.update(|xs| xs.retain(|(x, _)| x.id != id));
+        let copy = self.copy.get_untracked();
+        copy.remove(id);
+        let origin = self.origin.get_untracked();
+        if let Some(parent) = origin.parent_of(id).and_then(|x| copy.find(x)) {
+            parent.sons.update(|xs| xs.push(member));
+        }
+    }
+
+    // the stored and the edited subtree of every moved member
+    fn moved_subtrees(&self) -> Vec<(RawMember, RawMember)> {
+        let mut origin = self.origin.get_untracked();
+        self.moves
+            .get_untracked()
+            .into_iter()
+            .filter_map(|(member, _)| Some((origin.find_son(member.id)?.clone(), member.raw())))
+            .collect()
+    }
+
+    fn moved_ids(&self) -> Vec<Uuid> {
+        self.moves
+            .get_untracked()
+            .into_iter()
+            .map(|(member, _)| member.id)
+            .collect()
+    }
+
+    pub fn spouses(&self, id: Uuid) -> Vec<Marriage> {
+        self.marriages
+            .get()
+            .into_iter()
+            .filter(|x| x.spouse_of(id).is_some())
+            .collect()
+    }
+
+    pub fn set_spouses(&self, id: Uuid, marriages: Vec<Marriage>) {
+        self.marriages.update(|xs| {
+            xs.retain(|x| x.spouse_of(id).is_none());
+            xs.extend(marriages);
+        });
+    }
+
+    fn marriages_updates(&self) -> (Vec<Marriage>, Vec<Marriage>, Vec<Uuid>) {
+        let origin = self.origin_marriages.get_untracked();
+        let copy = self.marriages.get_untracked();
+        let created = copy
+            .iter()
+            .filter(|x| origin.iter().all(|y| y.id != x.id))
+            .cloned()
+            .collect();
+        let updated = copy
+            .iter()
+            .filter(|x| {
+                origin.iter().any(|y| {
+                    y.id == x.id
+                        && (y.start_date != x.start_date
+                            || y.end_date != x.end_date
+                            || y.status != x.status)
+                })
+            })
+            .cloned()
+            .collect();
+        let deleted = origin
+            .iter()
+            .filter(|x| copy.iter().all(|y| y.id != x.id))
+            .map(|x| x.id)
+            .collect();
+        (created, updated, deleted)
+    }
+
     fn updates(&self) -> Vec<SonlessRawMember> {
         let origin = self.origin.get_untracked();
         let copy = self.copy.get_untracked().raw();
 
         fn compare(origin: RawMember, copy: RawMember) -> Vec<SonlessRawMember> {
-            let first = if origin.name != copy.name || origin.is_male != copy.is_male {
+            let first = if origin.name != copy.name
+                || origin.is_male != copy.is_male
+                || origin.mother_id != copy.mother_id
+                || origin.birth != copy.birth
+                || origin.death != copy.death
+                || origin.names != copy.names
+            {
                 Some(copy.clone().sonless())
             } else {
                 None
@@ -57,7 +209,11 @@ impl Updates {
             }
             rest
         }
-        compare(origin, copy)
+        let mut updates = compare(origin, copy);
+        for (origin, copy) in self.moved_subtrees() {
+            updates.extend(compare(origin, copy));
+        }
+        updates
     }
 
     fn created(&self) -> Vec<(Uuid, RawMember)> {
@@ -80,10 +236,22 @@ impl Updates {
             }
             rest
         }
-        compare(origin, copy)
+        let moved = self.moved_ids();
+        let mut created = compare(origin, copy);
+        for (origin, copy) in self.moved_subtrees() {
+            created.extend(compare(origin, copy));
+        }
+        created.retain(|(_, x)| !moved.contains(&x.id));
+        created.extend(
+            self.adopted
+                .get_untracked()
+                .into_iter()
+                .map(|(member, parent_id)| (parent_id, member.raw())),
+        );
+        created
     }
 
-    fn deleted(&self) -> Vec<Uuid> {
+    fn deleted(&self) -> Vec<(Uuid, i32)> {
         let origin = self.origin.get_untracked();
         let copy = self.copy.get_untracked().raw();
 
@@ -102,82 +270,401 @@ impl Updates {
             }
             rest
         }
-        compare(origin, copy).into_iter().map(|x| x.id).collect()
+        let moved = self.moved_ids();
+        let mut deleted = compare(origin, copy);
+        for (origin, copy) in self.moved_subtrees() {
+            deleted.extend(compare(origin, copy));
+        }
+        deleted
+            .into_iter()
+            .filter(|x| !moved.contains(&x.id))
+            .map(|x| (x.id, x.version))
+            .collect()
     }
 
-    pub async fn commit(&self) -> Result<(), ServerFnError> {
+    pub async fn commit(&self) -> Result<Committed, ServerFnError> {
         let updated_members = self.updates();
+        let updated_ids = updated_members.iter().map(|x| x.id).collect::<Vec<_>>();
         let created_members = self.created();
         let deleted_members = self.deleted();
-        server_commit(ServerUpdates {
+        let mut origin = self.origin.get_untracked();
+        let moved_members = self
+            .moves
+            .get_untracked()
+            .into_iter()
+            .filter(|(member, _)| origin.find_son(member.id).is_some())
+            .map(|(member, parent_id)| MovedMember {
+                id: member.id,
+                parent_id,
+                version: member.version.get_untracked(),
+            })
+            .collect::<Vec<_>>();
+        let (created_marriages, updated_marriages, deleted_marriages) = self.marriages_updates();
+        let committed = server_commit(ServerUpdates {
             created_members,
             deleted_members,
             updated_members,
+            moved_members,
+            created_marriages,
+            updated_marriages,
+            deleted_marriages,
         })
         .await?;
-        self.origin.set(self.copy.get_untracked().raw());
-        Ok(())
+        match committed {
+            Committed::Applied => {
+                for id in updated_ids.into_iter().chain(self.moved_ids()) {
+                    if let Some(member) = self.find(id) {
+                        member.version.update(|x| *x += 1);
+                    }
+                }
+                self.moves.set(Vec::new());
+                self.adopted.set(Vec::new());
+                let copy = self.copy.get_untracked();
+                self.origin.set(copy.raw());
+                self.origin_marriages.set(self.marriages.get_untracked());
+            }
+            Committed::Proposed => self.discard(),
+            Committed::Conflicted(_) => (),
+        }
+        Ok(committed)
+    }
+
+    // keeps the local change of a member that someone else changed in the meantime,
+    // so that the next commit overwrites their version
+    pub fn keep_yours(&self, conflict: &Conflict) {
+        let Some(theirs) = conflict.theirs.as_ref().filter(|x| x.id == conflict.id) else {
+            return;
+        };
+        self.origin.update(|origin| {
+            if let Some(member) = origin.find_son(conflict.id) {
+                member.version = theirs.version;
+            }
+        });
+        if let Some(member) = self.find(conflict.id) {
+            member.version.set(theirs.version);
+        }
+    }
+
+    // drops the local change of a member in favour of what is stored
+    pub fn take_theirs(&self, conflict: &Conflict) {
+        let id = conflict.id;
+        self.unmove(id);
+        let copy = self.copy.get_untracked();
+        match conflict.theirs.as_ref() {
+            Some(theirs) if theirs.id == id => {
+                self.origin.update(|origin| {
+                    if let Some(member) = origin.find_son(id) {
+                        *member = theirs
+                            .clone()
+                            .with_sons(std::mem::take(&mut member.sons), member.unloaded_sons);
+                    }
+                });
+                match copy.find(id) {
+                    Some(member) => member.set(theirs.clone()),
+                    None => {
+                        let mut origin = self.origin.get_untracked();
+                        let parent = origin.parent_of(id).and_then(|x| copy.find(x));
+                        if let (Some(parent), Some(member)) = (parent, origin.find_son(id)) {
+                            let member = Member::from_raw(member.clone());
+                            parent.sons.update(|xs| xs.push(member));
+                        }
+                    }
+                }
+            }
+            Some(_) => {
+                let mut origin = self.origin.get_untracked();
+                match (origin.find_son(id), copy.find(id)) {
+                    (Some(stored), Some(member)) => member.set(stored.clone().sonless()),
+                    _ => copy.remove(id),
+                }
+            }
+            None => {
+                self.origin.update(|origin| origin.remove_son(id));
+                copy.remove(id);
+            }
+        }
+    }
+
+    pub fn expand(&self, member: Member, sons: Vec<RawMember>, marriages: Vec<Marriage>) {
+        let add_marriages = |xs: &mut Vec<Marriage>| {
+            for marriage in marriages.iter() {
+                if xs.iter().all(|x| x.id != marriage.id) {
+                    xs.push(marriage.clone());
+                }
+            }
+        };
+        self.origin_marriages.update(add_marriages);
+        self.marriages.update(add_marriages);
+        self.origin.update(|origin| {
+            if let Some(origin) = origin.find_son(member.id) {
+                origin.sons.extend(sons.clone());
+                origin.unloaded_sons = false;
+            }
+        });
+        member
+            .sons
+            .update(|xs| xs.extend(sons.into_iter().map(Member::from_raw)));
+        member.unloaded_sons.set(false);
     }
 
     pub fn discard(&self) {
+        self.moves.set(Vec::new());
+        self.adopted.set(Vec::new());
         self.copy.set(Member::from_raw(self.origin.get_untracked()));
+        self.marriages.set(self.origin_marriages.get_untracked());
     }
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServerUpdates {
+    pub created_members: Vec<(Uuid, RawMember)>,
+    pub deleted_members: Vec<(Uuid, i32)>,
+    pub updated_members: Vec<SonlessRawMember>,
+    #[serde(default)]
+    pub moved_members: Vec<MovedMember>,
+    pub created_marriages: Vec<Marriage>,
+    pub updated_marriages: Vec<Marriage>,
+    pub deleted_marriages: Vec<Uuid>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct ServerUpdates {
-    created_members: Vec<(Uuid, RawMember)>,
-    deleted_members: Vec<Uuid>,
-    updated_members: Vec<SonlessRawMember>,
+pub enum Committed {
+    Applied,
+    Proposed,
+    Conflicted(Vec<Conflict>),
+}
+
+#[cfg(feature = "ssr")]
+pub async fn authorized(
+    user: &crate::db::users::UserInfo,
+    updates: &ServerUpdates,
+) -> Result<bool, ServerFnError> {
+    use crate::db::{marriage, Pool, Postgres};
+    use leptos::expect_context;
+    let pool = expect_context::<Pool<Postgres>>();
+    let existing_marriages = updates
+        .updated_marriages
+        .iter()
+        .map(|x| x.id)
+        .chain(updates.deleted_marriages.iter().copied())
+        .collect::<Vec<_>>();
+    let Ok(existing_spouses) = marriage::spouses(&pool, &existing_marriages).await else {
+        return Err(ServerFnError::ServerError(
+            "error reading marriage".to_string(),
+        ));
+    };
+    let spouses = updates
+        .created_marriages
+        .iter()
+        .map(|x| (x.husband.id, x.wife.id))
+        .chain(existing_spouses)
+        .collect::<Vec<_>>();
+    let parents = updates
+        .created_members
+        .iter()
+        .map(|(parent_id, _)| *parent_id)
+        .collect::<Vec<_>>();
+    let members = updates
+        .updated_members
+        .iter()
+        .map(|x| x.id)
+        .chain(updates.deleted_members.iter().map(|(id, _)| *id))
+        .chain(
+            updates
+                .moved_members
+                .iter()
+                .flat_map(|x| [x.id, x.parent_id]),
+        )
+        .collect::<Vec<_>>();
+    let ids = parents
+        .iter()
+        .chain(members.iter())
+        .copied()
+        .chain(spouses.iter().flat_map(|(husband, wife)| [*husband, *wife]))
+        .collect();
+    let mut editable = crate::auth::editable(user, ids).await?;
+    if !parents.iter().all(|x| editable.contains(x)) {
+        return Ok(false);
+    }
+    editable.extend(
+        updates
+            .created_members
+            .iter()
+            .flat_map(|(_, member)| member.ids()),
+    );
+    let owns = |id: &Uuid| user.member_id == Some(*id);
+    Ok(updates
+        .updated_members
+        .iter()
+        .all(|x| editable.contains(&x.id) || owns(&x.id))
+        && updates
+            .deleted_members
+            .iter()
+            .all(|(id, _)| editable.contains(id))
+        && updates
+            .moved_members
+            .iter()
+            .all(|x| editable.contains(&x.id) && editable.contains(&x.parent_id))
+        && spouses
+            .iter()
+            .all(|(husband, wife)| editable.contains(husband) || editable.contains(wife)))
+}
+
+// reviewing someone else's updates takes a branch admin over everything they touch,
+// the same rights require_branch_admin asks for
+#[cfg(feature = "ssr")]
+pub async fn reviewable(
+    user: &crate::db::users::UserInfo,
+    updates: &ServerUpdates,
+) -> Result<bool, ServerFnError> {
+    use crate::db::users::Role;
+    match user.role {
+        Role::SuperAdmin => Ok(true),
+        Role::BranchAdmin => authorized(user, updates).await,
+        Role::Viewer | Role::Editor => Ok(false),
+    }
 }
 
 #[server(encoding = "Cbor")]
-async fn server_commit(updates: ServerUpdates) -> Result<(), ServerFnError> {
-    use crate::db::{member, Pool, Postgres};
+async fn server_commit(updates: ServerUpdates) -> Result<Committed, ServerFnError> {
+    let user = crate::auth::require_user().await?;
+    submit(&user, updates).await
+}
+
+// applies the updates when the user may make them, otherwise queues them for review
+#[cfg(feature = "ssr")]
+pub async fn submit(
+    user: &crate::db::users::UserInfo,
+    updates: ServerUpdates,
+) -> Result<Committed, ServerFnError> {
+    use crate::db::{proposals, Pool, Postgres};
     use leptos::expect_context;
     let pool = expect_context::<Pool<Postgres>>();
+    if !authorized(user, &updates).await? {
+        let Ok(_) = proposals::create(&pool, user.id, &updates).await else {
+            return Err(ServerFnError::ServerError(
+                "error creating proposal".to_string(),
+            ));
+        };
+        return Ok(Committed::Proposed);
+    }
     let mut transaction = pool.begin().await?;
+    let conflicts = apply(&mut transaction, updates, user.id).await?;
+    if !conflicts.is_empty() {
+        return Ok(Committed::Conflicted(conflicts));
+    }
+    transaction.commit().await?;
+    Ok(Committed::Applied)
+}
+
+// applies the updates unless some of them were made against outdated members,
+// in which case nothing is applied and the conflicts are returned
+#[cfg(feature = "ssr")]
+pub async fn apply(
+    transaction: &mut crate::db::Transaction<'_, crate::db::Postgres>,
+    updates: ServerUpdates,
+    author_id: Uuid,
+) -> Result<Vec<Conflict>, ServerFnError> {
+    use crate::db::{history::Batch, marriage, member};
+    let Ok(conflicts) = member::conflicts(
+        transaction,
+        &updates.created_members,
+        &updates.updated_members,
+        &updates.deleted_members,
+        &updates.moved_members,
+    )
+    .await
+    else {
+        return Err(ServerFnError::ServerError(
+            "error checking conflicts".to_string(),
+        ));
+    };
+    if !conflicts.is_empty() {
+        return Ok(conflicts);
+    }
+    let batch = Batch::new(author_id);
     for (parent_id, member) in updates.created_members {
-        let Ok(_) = member::create(&mut transaction, member, Some(parent_id)).await else {
+        let Ok(_) = member::create(transaction, member, Some(parent_id), &batch).await else {
             return Err(ServerFnError::ServerError(
                 "error creating member".to_string(),
             ));
         };
     }
-    for id in updates.deleted_members {
-        let Ok(_) = member::delete(&mut transaction, id).await else {
+    for marriage in updates.created_marriages {
+        let Ok(_) = marriage::create(transaction, marriage).await else {
             return Err(ServerFnError::ServerError(
-                "error deleting member".to_string(),
+                "error creating marriage".to_string(),
             ));
         };
     }
-    let Ok(_) = member::update(&mut transaction, updates.updated_members).await else {
+    let Ok(_) = marriage::update(transaction, updates.updated_marriages).await else {
+        return Err(ServerFnError::ServerError(
+            "error updating marriage".to_string(),
+        ));
+    };
+    for id in updates.deleted_marriages {
+        let Ok(_) = marriage::delete(transaction, id).await else {
+            return Err(ServerFnError::ServerError(
+                "error deleting marriage".to_string(),
+            ));
+        };
+    }
+    let Ok(_) = member::update(transaction, updates.updated_members, &batch).await else {
         return Err(ServerFnError::ServerError(
             "error updating member".to_string(),
         ));
     };
-
-    transaction.commit().await?;
-    Ok(())
+    for moved in updates.moved_members.iter() {
+        let Ok(_) = member::reparent(transaction, moved, &batch).await else {
+            return Err(ServerFnError::ServerError(
+                "error moving member".to_string(),
+            ));
+        };
+    }
+    for (id, version) in updates.deleted_members {
+        let Ok(_) = member::delete(transaction, id, version, &batch).await else {
+            return Err(ServerFnError::ServerError(
+                "error deleting member".to_string(),
+            ));
+        };
+    }
+    Ok(Vec::new())
 }
 
 pub trait Rm {
-    fn find_son(&self, id: Uuid) -> Option<&RawMember>;
+    fn find_son(&mut self, id: Uuid) -> Option<&mut RawMember>;
+    fn parent_of(&self, id: Uuid) -> Option<Uuid>;
+    fn remove_son(&mut self, id: Uuid);
     fn sonless(self) -> SonlessRawMember;
+    fn ids(&self) -> Vec<Uuid>;
 }
 
 impl Rm for RawMember {
-    fn find_son(&self, id: Uuid) -> Option<&RawMember> {
+    fn find_son(&mut self, id: Uuid) -> Option<&mut RawMember> {
         if self.id == id {
             return Some(self);
-        } else {
-            for son in self.sons.iter() {
-                if let Some(son) = son.find_son(id) {
-                    return Some(son);
-                };
-            }
         }
-        None
+        self.sons.iter_mut().find_map(|son| son.find_son(id))
+    }
+
+    fn parent_of(&self, id: Uuid) -> Option<Uuid> {
+        if self.sons.iter().any(|son| son.id == id) {
+            return Some(self.id);
+        }
+        self.sons.iter().find_map(|son| son.parent_of(id))
+    }
+
+    fn remove_son(&mut self, id: Uuid) {
+        self.sons.retain(|son| son.id != id);
+        for son in self.sons.iter_mut() {
+            son.remove_son(id);
+        }
+    }
+
+    fn ids(&self) -> Vec<Uuid> {
+        std::iter::once(self.id)
+            .chain(self.sons.iter().flat_map(|son| son.ids()))
+            .collect()
     }
 
     fn sonless(self) -> SonlessRawMember {
@@ -185,6 +672,11 @@ impl Rm for RawMember {
             id: self.id,
             name: self.name,
             is_male: self.is_male,
+            mother_id: self.mother_id,
+            birth: self.birth,
+            death: self.death,
+            names: self.names,
+            version: self.version,
         }
     }
 }
@@ -195,7 +687,13 @@ impl Member {
             id: Uuid::new_v4(),
             name: RwSignal::new(name),
             is_male: RwSignal::new(true),
+            mother_id: Default::default(),
+            birth: Default::default(),
+            death: Default::default(),
+            names: Default::default(),
+            version: RwSignal::new(1),
             sons: Default::default(),
+            unloaded_sons: Default::default(),
             action: Default::default(),
         }
     }
@@ -204,14 +702,26 @@ impl Member {
             id,
             name,
             is_male,
+            mother_id,
+            birth,
+            death,
+            names,
+            version,
             sons,
+            unloaded_sons,
         }: RawMember,
     ) -> Self {
         Self {
             id,
             name: RwSignal::new(name),
             is_male: RwSignal::new(is_male),
+            mother_id: RwSignal::new(mother_id),
+            birth: RwSignal::new(birth),
+            death: RwSignal::new(death),
+            names: RwSignal::new(names),
+            version: RwSignal::new(version),
             sons: RwSignal::new(sons.into_iter().map(Member::from_raw).collect()),
+            unloaded_sons: RwSignal::new(unloaded_sons),
             action: Default::default(),
         }
     }
@@ -220,21 +730,59 @@ impl Member {
             id: self.id,
             name: self.name.get_untracked(),
             is_male: self.is_male.get_untracked(),
+            mother_id: self.mother_id.get_untracked(),
+            birth: self.birth.get_untracked(),
+            death: self.death.get_untracked(),
+            names: self.names.get_untracked(),
+            version: self.version.get_untracked(),
             sons: self
                 .sons
                 .get_untracked()
                 .into_iter()
                 .map(|x| x.raw())
                 .collect::<Vec<_>>(),
+            unloaded_sons: self.unloaded_sons.get_untracked(),
+        }
+    }
+    pub fn sonless_raw(self) -> SonlessRawMember {
+        SonlessRawMember {
+            id: self.id,
+            name: self.name.get_untracked(),
+            is_male: self.is_male.get_untracked(),
+            mother_id: self.mother_id.get_untracked(),
+            birth: self.birth.get_untracked(),
+            death: self.death.get_untracked(),
+            names: self.names.get_untracked(),
+            version: self.version.get_untracked(),
+        }
+    }
+
+    pub fn set(&self, member: SonlessRawMember) {
+        self.name.set(member.name);
+        self.is_male.set(member.is_male);
+        self.mother_id.set(member.mother_id);
+        self.birth.set(member.birth);
+        self.death.set(member.death);
+        self.names.set(member.names);
+        self.version.set(member.version);
+    }
+
+    pub fn find(&self, id: Uuid) -> Option<Member> {
+        if self.id == id {
+            return Some(*self);
+        }
+        self.sons
+            .get_untracked()
+            .into_iter()
+            .find_map(|son| son.find(id))
+    }
+
+    pub fn remove(&self, id: Uuid) {
+        self.sons.update(|xs| xs.retain(|son| son.id != id));
+        for son in self.sons.get_untracked() {
+            son.remove(id);
         }
     }
-    // pub fn sonless_raw(self) -> SonlessRawMember {
-    //     SonlessRawMember {
-    //         id: self.id,
-    //         name: self.name.get_untracked(),
-    //         is_male: self.is_male.get_untracked(),
-    //     }
-    // }
 
     fn with_sons(&self, names: &mut Vec<String>) {
         let name = names.pop();
@@ -1,9 +1,30 @@
-use super::member;
+use std::str::FromStr;
+
+use super::{claims::claim_member, member, search::MemberSearch};
+use crate::calendar::{Calendar, DatePrecision, MemberDate};
+use crate::db::{
+    marriage::{Marriage, MarriageStatus},
+    member::{NameParts, SonlessRawMember},
+};
 
 use leptos::*;
+use leptos_router::A;
 
 use uuid::Uuid;
 
+#[derive(Clone, Copy)]
+pub struct Parent(pub Option<member::Member>);
+
+#[server(encoding = "Cbor")]
+async fn get_wives(husband: Uuid) -> Result<Vec<SonlessRawMember>, ServerFnError> {
+    use crate::db::{member::wives, Pool, Postgres};
+    let pool = expect_context::<Pool<Postgres>>();
+    match wives(&pool, husband).await {
+        Ok(wives) => Ok(wives),
+        Err(err) => Err(ServerFnError::ServerError(err.to_string())),
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct ActionsWaitlist(RwSignal<Vec<Uuid>>);
 
@@ -33,6 +54,8 @@ pub fn Action() -> impl IntoView {
                 member::Action::Add => view! { <Add/> },
                 member::Action::Remove => view! { <Remove/> },
                 member::Action::Update => view! { <Update/> },
+                member::Action::Marriages => view! { <Marriages/> },
+                member::Action::Move => view! { <Move/> },
             })
         } else {
             None
@@ -59,14 +82,57 @@ fn Preview() -> impl IntoView {
     let member = expect_context::<member::Member>();
     let actions_waitlist = expect_context::<ActionsWaitlist>();
     let redraw = move |_| actions_waitlist.redraw(member.id);
+    let claimed = RwSignal::new(None::<&'static str>);
+    let claim = move |_| {
+        spawn_local(async move {
+            let message = match claim_member(member.id).await {
+                Ok(_) => "تم ارسال الطلب للتحقق",
+                Err(_) => "يجب تسجيل الدخول اولا",
+            };
+            claimed.set(Some(message));
+        });
+    };
     view! {
         <div
           class="fixed top-[50%] left-[50%] translate-x-[-50%] translate-y-[-50%] text-3xl text-pretty text-zinc-300 rounded-lg bg-gray-700 border-gray-400 hover:border-gray-700 grid justify-content-center justify-items-center gap-5 p-5 mx-32 my-10 border-4 z-10"
         >
           <h2 class="text-center">{move || member.name.get()}</h2>
+          <LifeDates/>
           <AButton value="اضافة ابن".to_string() action=member::Action::Add/>
           <AButton value="حذف الابن".to_string() action=member::Action::Remove/>
           <AButton value="تحديث بيانات".to_string() action=member::Action::Update/>
+          <AButton value="الزواج".to_string() action=member::Action::Marriages/>
+          <AButton value="نقل الى اب اخر".to_string() action=member::Action::Move/>
+          <A
+              href=format!("/nasab/{}", member.id)
+              class="p-5 w-96 m-2 border-2 border-gray-400 bg-gray-950 hover:border-gray-950 rounded-lg text-center"
+          >
+              النسب
+          </A>
+          <A
+              href=format!("/relation?first={}", member.id)
+              class="p-5 w-96 m-2 border-2 border-gray-400 bg-gray-950 hover:border-gray-950 rounded-lg text-center"
+          >
+              صلة القرابة
+          </A>
+          <A
+              href=format!("/permissions/{}", member.id)
+              class="p-5 w-96 m-2 border-2 border-gray-400 bg-gray-950 hover:border-gray-950 rounded-lg text-center"
+          >
+              الصلاحيات
+          </A>
+          <A
+              href=format!("/history/{}", member.id)
+              class="p-5 w-96 m-2 border-2 border-gray-400 bg-gray-950 hover:border-gray-950 rounded-lg text-center"
+          >
+              السجل
+          </A>
+          <button
+              class="p-5 w-96 m-2 border-2 border-gray-400 bg-gray-950 hover:border-gray-950 rounded-lg"
+              on:click=claim
+          >
+              {move || claimed.get().unwrap_or("هذا انا")}
+          </button>
           <button
               class="p-5 w-96 border-2 hover:border-red-950 bg-red-950 border-red-400 rounded-lg"
               on:click=redraw
@@ -77,6 +143,41 @@ fn Preview() -> impl IntoView {
     }
 }
 
+#[component]
+fn LifeDates() -> impl IntoView {
+    let member = expect_context::<member::Member>();
+    let calendar = RwSignal::new(Calendar::default());
+    let toggle = move |_| {
+        calendar.update(|x| {
+            *x = match x {
+                Calendar::Gregorian => Calendar::Hijri,
+                Calendar::Hijri => Calendar::Gregorian,
+            }
+        })
+    };
+    let line = move |label: &'static str, date: RwSignal<Option<MemberDate>>| {
+        move || {
+            date.get()
+                .map(|x| view! { <p>{label}": "{x.display(calendar.get())}</p> })
+        }
+    };
+
+    view! {
+      <Show when=move || member.birth.get().is_some() || member.death.get().is_some()>
+        <div class="flex flex-col items-center gap-2 text-2xl">
+          {line("الميلاد", member.birth)}
+          {line("الوفاة", member.death)}
+          <button on:click=toggle class="px-3 text-xl rounded-lg border-2 border-gray-400 bg-gray-950">
+            {move || match calendar.get() {
+                Calendar::Gregorian => "عرض بالهجري",
+                Calendar::Hijri => "عرض بالميلادي",
+            }}
+          </button>
+        </div>
+      </Show>
+    }
+}
+
 #[component]
 fn ActionDiv<F>(submit: F, children: Children) -> impl IntoView
 where
@@ -113,12 +214,40 @@ where
     }
 }
 
+// sons left unloaded may already carry the names being added, so they are loaded
+// along the lineage for add_son to merge into them instead of duplicating them
+async fn load_lineage(
+    updates: RwSignal<member::Updates>,
+    parent: member::Member,
+    lineage: member::Member,
+) -> Result<(), ServerFnError> {
+    let (mut parent, mut son) = (parent, Some(lineage));
+    while let Some(current) = son {
+        if parent.unloaded_sons.get_untracked() {
+            super::load_sons(updates, parent).await?;
+        }
+        let name = current.name.get_untracked();
+        let Some(same) = parent
+            .sons
+            .get_untracked()
+            .into_iter()
+            .find(|x| x.name.get_untracked() == name)
+        else {
+            break;
+        };
+        parent = same;
+        son = current.sons.get_untracked().first().copied();
+    }
+    Ok(())
+}
+
 #[component]
 fn Add() -> impl IntoView {
     let is_only = RwSignal::new(true);
     let names = RwSignal::new(String::new());
     let select_ref = create_node_ref::<html::Select>();
     let member = expect_context::<member::Member>();
+    let mother = RwSignal::new(None);
     let on_input = move |ev| {
         let value = event_target_value(&ev);
         if value.contains(',') {
@@ -128,12 +257,20 @@ fn Add() -> impl IntoView {
         }
         names.set(value);
     };
+    let updates = expect_context::<RwSignal<member::Updates>>();
     let submit = move || {
         let value: bool = select_ref.get().unwrap().value().parse().unwrap();
-        names.get().split(',').for_each(|name| {
-            let new_member = member::Member::create_from_name(name);
-            new_member.is_male.set(value);
-            member.add_son(new_member);
+        let (names, mother) = (names.get(), mother.get_untracked());
+        spawn_local(async move {
+            for name in names.split(',') {
+                let new_member = member::Member::create_from_name(name);
+                new_member.is_male.set(value);
+                new_member.mother_id.set(mother);
+                if load_lineage(updates, member, new_member).await.is_err() {
+                    return;
+                }
+                member.add_son(new_member);
+            }
         });
     };
 
@@ -156,6 +293,7 @@ fn Add() -> impl IntoView {
               {move || if is_only.get()  {"انثي"} else {"اناث"}}
           </option>
       </select>
+      <MotherSelect father=Some(member.id) mother/>
     </ActionDiv>
     }
 }
@@ -223,8 +361,17 @@ fn Remove() -> impl IntoView {
 #[component]
 fn Update() -> impl IntoView {
     let member = expect_context::<member::Member>();
+    let Parent(parent) = expect_context::<Parent>();
+    let mother = RwSignal::new(member.mother_id.get_untracked());
+    let birth = RwSignal::new(member.birth.get_untracked());
+    let death = RwSignal::new(member.death.get_untracked());
+    let names = member.names.get_untracked();
     let name_ref = create_node_ref::<html::Input>();
     let gender_ref = create_node_ref::<html::Select>();
+    let kunya = RwSignal::new(names.kunya.unwrap_or_default());
+    let laqab = RwSignal::new(names.laqab.unwrap_or_default());
+    let nisba = RwSignal::new(names.nisba.unwrap_or_default());
+    let alternate = RwSignal::new(names.alternate.join("، "));
 
     let submit = move || {
         let name = name_ref.get().unwrap().value().trim().to_string();
@@ -232,8 +379,26 @@ fn Update() -> impl IntoView {
         if name.is_empty() {
             return;
         }
+        let optional = |value: RwSignal<String>| {
+            Some(value.get_untracked().trim().to_string()).filter(|x| !x.is_empty())
+        };
+        let alternate = alternate
+            .get_untracked()
+            .split(['،', ','])
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty())
+            .collect();
         member.name.set(name);
         member.is_male.set(is_male);
+        member.names.set(NameParts {
+            kunya: optional(kunya),
+            laqab: optional(laqab),
+            nisba: optional(nisba),
+            alternate,
+        });
+        member.mother_id.set(mother.get_untracked());
+        member.birth.set(birth.get_untracked());
+        member.death.set(death.get_untracked());
     };
     view! {
     <ActionDiv submit>
@@ -249,6 +414,365 @@ fn Update() -> impl IntoView {
         <option value="true" class="text-center p-5 text-4xl" selected>ذكر</option>
         <option value="false" class="text-center p-5 text-4xl">انثي</option>
       </select>
+      <NamePartInput placeholder="الكنية (ابو فلان)" value=kunya/>
+      <NamePartInput placeholder="اللقب" value=laqab/>
+      <NamePartInput placeholder="النسبة" value=nisba/>
+      <NamePartInput placeholder="اسماء اخرى (مفصولة بفاصلة)" value=alternate/>
+      <MotherSelect father=parent.map(|x| x.id) mother/>
+      <DateInput label="الميلاد" value=birth/>
+      <DateInput label="الوفاة" value=death/>
+    </ActionDiv>
+    }
+}
+
+#[component]
+fn NamePartInput(placeholder: &'static str, value: RwSignal<String>) -> impl IntoView {
+    view! {
+      <input
+          class="col-span-4 placeholder:text-center placeholder-gray-400 bg-gray-800 border-gray-500 hover:border-gray-800 text-center border-2 mx-5 p-2 text-2xl rounded-lg w-96"
+          placeholder=placeholder
+          prop:value=move || value.get()
+          on:input=move |ev| value.set(event_target_value(&ev))
+      />
+    }
+}
+
+#[component]
+fn DateInput(label: &'static str, value: RwSignal<Option<MemberDate>>) -> impl IntoView {
+    let calendar = RwSignal::new(Calendar::default());
+    let precision = RwSignal::new(
+        value
+            .get_untracked()
+            .map(|x| x.precision)
+            .unwrap_or_default(),
+    );
+    let (year, month, day) = match value.get_untracked() {
+        Some(x) => {
+            let (year, month, day) = Calendar::default().parts(x.date);
+            (year.to_string(), month, day.to_string())
+        }
+        None => (String::new(), 1, String::new()),
+    };
+    let year = RwSignal::new(year);
+    let month = RwSignal::new(month);
+    let day = RwSignal::new(day);
+    let invalid = RwSignal::new(false);
+
+    let sync = move || {
+        let year = year.get_untracked();
+        if year.trim().is_empty() {
+            invalid.set(false);
+            value.set(None);
+            return;
+        }
+        let precision = precision.get_untracked();
+        let (month, day) = match precision {
+            DatePrecision::Exact => (
+                month.get_untracked(),
+                day.get_untracked().trim().parse().unwrap_or(0),
+            ),
+            _ => (1, 1),
+        };
+        let date = year
+            .trim()
+            .parse()
+            .ok()
+            .and_then(|year| calendar.get_untracked().to_date(year, month, day));
+        invalid.set(date.is_none());
+        if let Some(date) = date {
+            value.set(Some(MemberDate { date, precision }));
+        }
+    };
+    let on_calendar = move |ev| {
+        let Ok(new) = Calendar::from_str(&event_target_value(&ev)) else {
+            return;
+        };
+        calendar.set(new);
+        if let Some(x) = value.get_untracked() {
+            let (y, m, d) = new.parts(x.date);
+            year.set(y.to_string());
+            month.set(m);
+            day.set(d.to_string());
+        }
+    };
+    let on_precision = move |ev| {
+        let Ok(new) = DatePrecision::from_str(&event_target_value(&ev)) else {
+            return;
+        };
+        precision.set(new);
+        sync();
+    };
+
+    let precision_option = move |x: DatePrecision| {
+        view! { <option value=x.as_str() selected=move || precision.get() == x>{x.label()}</option> }
+    };
+    let is_exact = move || precision.get() == DatePrecision::Exact;
+
+    view! {
+      <div class="col-span-4 flex flex-row flex-wrap items-center justify-center gap-3 text-2xl">
+        <span>{label}</span>
+        <select on:change=on_calendar class="bg-gray-800 rounded-lg p-1">
+          <option value=Calendar::Gregorian.as_str() selected>"ميلادي"</option>
+          <option value=Calendar::Hijri.as_str()>"هجري"</option>
+        </select>
+        <select on:change=on_precision class="bg-gray-800 rounded-lg p-1">
+          {precision_option(DatePrecision::Exact)}
+          {precision_option(DatePrecision::Year)}
+          {precision_option(DatePrecision::Approximate)}
+          {precision_option(DatePrecision::Before)}
+          {precision_option(DatePrecision::After)}
+        </select>
+        <Show when=is_exact>
+          <input
+              type="number"
+              placeholder="اليوم"
+              prop:value=move || day.get()
+              on:change=move |ev| {
+                  day.set(event_target_value(&ev));
+                  sync();
+              }
+              class="bg-gray-800 rounded-lg p-1 w-20 text-center"
+          />
+          <select
+              on:change=move |ev| {
+                  month.set(event_target_value(&ev).parse().unwrap_or(1));
+                  sync();
+              }
+              class="bg-gray-800 rounded-lg p-1"
+          >
+            {move || {
+                calendar
+                    .get()
+                    .months()
+                    .into_iter()
+                    .zip(1..)
+                    .map(|(name, i)| {
+                        view! { <option value=i selected=move || month.get() == i>{name}</option> }
+                    })
+                    .collect_view()
+            }}
+          </select>
+        </Show>
+        <input
+            type="number"
+            placeholder="السنة"
+            prop:value=move || year.get()
+            on:change=move |ev| {
+                year.set(event_target_value(&ev));
+                sync();
+            }
+            class="bg-gray-800 rounded-lg p-1 w-28 text-center"
+        />
+        <span>{move || calendar.get().suffix()}</span>
+        <Show when=move || invalid.get()>
+          <span class="text-red-400">"تاريخ غير صحيح"</span>
+        </Show>
+      </div>
+    }
+}
+
+#[component]
+fn MotherSelect(father: Option<Uuid>, mother: RwSignal<Option<Uuid>>) -> impl IntoView {
+    let current = mother.get_untracked();
+    let wives = Resource::once(move || async move {
+        match father {
+            Some(father) => get_wives(father).await.unwrap_or_default(),
+            None => Vec::new(),
+        }
+    });
+    let picked = RwSignal::new(Vec::<SonlessRawMember>::new());
+    let options = move || {
+        let mut options = wives.get().unwrap_or_default();
+        for wife in picked.get() {
+            if options.iter().all(|x| x.id != wife.id) {
+                options.push(wife);
+            }
+        }
+        options
+    };
+    let is_listed = move |id: Uuid| options().iter().any(|x| x.id == id);
+
+    let on_change = move |ev| mother.set(Uuid::from_str(&event_target_value(&ev)).ok());
+    let pick = Callback::new(move |wife: SonlessRawMember| {
+        if wife.is_male {
+            return;
+        }
+        mother.set(Some(wife.id));
+        picked.update(|xs| xs.push(wife));
+    });
+
+    view! {
+      <select
+        on:change=on_change
+        class="col-span-4 bg-gray-800 border-gray-500 hover:border-gray-800 text-center border-2 mx-5 text-4xl rounded-lg w-96"
+      >
+        <option value="" class="text-center p-5 text-4xl" selected=move || mother.get().is_none()>
+          الام غير معروفة
+        </option>
+        {move || current.filter(|id| !is_listed(*id)).map(|id| view! {
+          <option value=id.to_string() class="text-center p-5 text-4xl" selected=move || mother.get() == Some(id)>
+            الام الحالية
+          </option>
+        })}
+        <For
+            each=options
+            key=|x| x.id
+            let:wife
+        >
+          <option
+            value=wife.id.to_string()
+            class="text-center p-5 text-4xl"
+            selected=move || mother.get() == Some(wife.id)
+          >
+            {wife.name}
+          </option>
+        </For>
+      </select>
+      <div class="col-span-4 text-xl">
+        <MemberSearch placeholder="بحث عن الام" on_pick=pick/>
+      </div>
+    }
+}
+
+#[component]
+fn Marriages() -> impl IntoView {
+    let member = expect_context::<member::Member>();
+    let updates = expect_context::<RwSignal<member::Updates>>();
+    let marriages = RwSignal::new(updates.get_untracked().spouses(member.id));
+
+    let pick = Callback::new(move |spouse: SonlessRawMember| {
+        let this = member.sonless_raw();
+        if spouse.is_male == this.is_male {
+            return;
+        }
+        let marriage = if this.is_male {
+            Marriage::new(this, spouse)
+        } else {
+            Marriage::new(spouse, this)
+        };
+        marriages.update(|xs| xs.push(marriage));
+    });
+
+    let submit = move || {
+        updates
+            .get_untracked()
+            .set_spouses(member.id, marriages.get_untracked());
+    };
+
+    view! {
+    <ActionDiv submit>
+      <div class="col-span-4 grid gap-3 text-xl">
+        <For
+            each=move || marriages.get()
+            key=|x| x.id
+            let:marriage
+        >
+          <MarriageRow marriage member_id=member.id marriages/>
+        </For>
+      </div>
+      <div class="col-span-4 text-xl">
+        <MemberSearch placeholder="اضافة زوج او زوجة" on_pick=pick/>
+      </div>
+    </ActionDiv>
+    }
+}
+
+#[component]
+fn Move() -> impl IntoView {
+    let member = expect_context::<member::Member>();
+    let updates = expect_context::<RwSignal<member::Updates>>();
+    let target = RwSignal::new(None::<SonlessRawMember>);
+
+    let pick = Callback::new(move |parent: SonlessRawMember| {
+        if parent.is_male && parent.id != member.id {
+            target.set(Some(parent));
+        }
+    });
+
+    let submit = move || {
+        if let Some(parent) = target.get_untracked() {
+            updates.get_untracked().move_member(member, parent.id);
+        }
+    };
+
+    view! {
+    <ActionDiv submit>
+      <p class="col-span-4 text-2xl">
+        {move || match target.get() {
+            Some(parent) => format!("النقل الى: {}", parent.name),
+            None => "اختر الاب الجديد".to_string(),
+        }}
+      </p>
+      <div class="col-span-4 text-xl">
+        <MemberSearch placeholder="بحث عن الاب" on_pick=pick/>
+      </div>
     </ActionDiv>
     }
 }
+
+#[component]
+fn MarriageRow(
+    marriage: Marriage,
+    member_id: Uuid,
+    marriages: RwSignal<Vec<Marriage>>,
+) -> impl IntoView {
+    let id = marriage.id;
+    let spouse = marriage.spouse_of(member_id).cloned().unwrap_or_default();
+    let edit = move |f: &dyn Fn(&mut Marriage)| {
+        marriages.update(|xs| {
+            if let Some(marriage) = xs.iter_mut().find(|x| x.id == id) {
+                f(marriage);
+            }
+        })
+    };
+    let on_start = move |ev| {
+        let date = event_target_value(&ev).parse().ok();
+        edit(&|x| x.start_date = date);
+    };
+    let on_end = move |ev| {
+        let date = event_target_value(&ev).parse().ok();
+        edit(&|x| x.end_date = date);
+    };
+    let on_status = move |ev| {
+        let Ok(status) = MarriageStatus::from_str(&event_target_value(&ev)) else {
+            return;
+        };
+        edit(&|x| x.status = status);
+    };
+    let remove = move |_| marriages.update(|xs| xs.retain(|x| x.id != id));
+
+    let status_option = move |status: MarriageStatus, label: &'static str| {
+        view! {
+          <option value=status.as_str() selected=marriage.status == status>{label}</option>
+        }
+    };
+
+    view! {
+      <div class="flex flex-row flex-wrap items-center gap-3 p-3 rounded-lg bg-gray-800">
+        <span class="w-40 text-center">{spouse.name}</span>
+        <input
+            type="date"
+            on:change=on_start
+            value=marriage.start_date.map(|x| x.to_string())
+            class="bg-gray-700 rounded-lg p-1"
+        />
+        <input
+            type="date"
+            on:change=on_end
+            value=marriage.end_date.map(|x| x.to_string())
+            class="bg-gray-700 rounded-lg p-1"
+        />
+        <select on:change=on_status class="bg-gray-700 rounded-lg p-1">
+          {status_option(MarriageStatus::Married, "قائم")}
+          {status_option(MarriageStatus::Divorced, "طلاق")}
+          {status_option(MarriageStatus::Widowed, "وفاة")}
+        </select>
+        <button
+            on:click=remove
+            class="px-3 rounded-lg border-2 border-red-600 bg-red-950 hover:border-red-950"
+        >
+          حذف
+        </button>
+      </div>
+    }
+}
@@ -0,0 +1,103 @@
+use std::str::FromStr;
+
+use crate::db::member::{NameParts, SonlessRawMember};
+use leptos::*;
+use leptos_router::{use_params_map, A};
+use uuid::Uuid;
+
+#[server(encoding = "Cbor")]
+async fn get_ancestors(id: Uuid) -> Result<Vec<SonlessRawMember>, ServerFnError> {
+    use crate::db::{member::ancestors, Pool, Postgres};
+    let pool = expect_context::<Pool<Postgres>>();
+    match ancestors(&pool, id).await {
+        Ok(chain) => Ok(chain),
+        Err(err) => Err(ServerFnError::ServerError(err.to_string())),
+    }
+}
+
+#[component]
+pub fn Nasab() -> impl IntoView {
+    let params = use_params_map();
+    let id = move || {
+        let id = params.with(|x| x.get("id").cloned()).unwrap();
+        Uuid::from_str(&id).unwrap()
+    };
+
+    let chain = Resource::new(id, get_ancestors);
+
+    view! {
+        <section class="grid justify-items-center overflow-auto">
+            <h1 class="text-center m-5 text-3xl">النسب</h1>
+            <Suspense>
+                {move || {
+                    chain
+                        .get()
+                        .and_then(Result::ok)
+                        .map(|chain| {
+                            let alternate = chain
+                                .first()
+                                .map(|x| x.names.alternate.join("، "))
+                                .filter(|x| !x.is_empty())
+                                .map(|x| view! { <p class="text-center text-xl">"ويعرف ايضا بـ: "{x}</p> });
+                            view! {
+                                <p class="text-center text-2xl leading-loose m-5">
+                                    <Lineage chain/>
+                                </p>
+                                {alternate}
+                            }
+                        })
+                }}
+            </Suspense>
+        </section>
+    }
+}
+
+#[component]
+pub fn Lineage(chain: Vec<SonlessRawMember>) -> impl IntoView {
+    let (kunya, titles) = name_parts(chain.first());
+    let last = chain.len().saturating_sub(1);
+    let lineage = chain
+        .into_iter()
+        .enumerate()
+        .map(|(index, member)| {
+            let relation =
+                (index != last).then_some(if member.is_male { " بن " } else { " بنت " });
+            view! {
+                <A href=format!("/exists/{}", member.id) class="hover:underline">{member.name}</A>
+                {relation}
+            }
+        })
+        .collect_view();
+    view! { {kunya}{lineage}{titles} }
+}
+
+fn name_parts(member: Option<&SonlessRawMember>) -> (String, String) {
+    let NameParts {
+        kunya,
+        laqab,
+        nisba,
+        ..
+    } = member.map(|x| x.names.clone()).unwrap_or_default();
+    let kunya = kunya.map(|x| format!("{x} ")).unwrap_or_default();
+    let titles = [laqab, nisba]
+        .into_iter()
+        .flatten()
+        .map(|x| format!(" {x}"))
+        .collect();
+    (kunya, titles)
+}
+
+pub fn nasab_string(chain: &[SonlessRawMember]) -> String {
+    let (kunya, titles) = name_parts(chain.first());
+    let last = chain.len().saturating_sub(1);
+    let lineage = chain
+        .iter()
+        .enumerate()
+        .map(|(index, member)| match (index != last, member.is_male) {
+            (true, true) => format!("{} بن ", member.name),
+            (true, false) => format!("{} بنت ", member.name),
+            (false, _) => member.name.clone(),
+        })
+        .collect::<String>();
+    format!("{kunya}{lineage}{titles}")
+}
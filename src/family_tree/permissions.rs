@@ -0,0 +1,156 @@
+use std::str::FromStr;
+
+use super::nasab::Lineage;
+use crate::db::{member::SonlessRawMember, users::UserInfo};
+use leptos::*;
+use leptos_router::use_params_map;
+use uuid::Uuid;
+
+// viewers edit nothing, branches included, until their role is raised
+const VIEWER_GRANT: &str = "المستخدم مشاهد فقط، ارفع دوره الى محرر قبل منحه الفرع";
+
+#[server(encoding = "Cbor")]
+async fn get_grants(root: Uuid) -> Result<(Vec<SonlessRawMember>, Vec<UserInfo>), ServerFnError> {
+    use crate::db::{member, users, Pool, Postgres};
+    crate::auth::require_branch_admin(root).await?;
+    let pool = expect_context::<Pool<Postgres>>();
+    let Ok(chain) = member::ancestors(&pool, root).await else {
+        return Err(ServerFnError::ServerError(
+            "error reading member".to_string(),
+        ));
+    };
+    let Ok(users) = users::granted(&pool, root).await else {
+        return Err(ServerFnError::ServerError(
+            "error reading grants".to_string(),
+        ));
+    };
+    Ok((chain, users))
+}
+
+#[server(encoding = "Cbor")]
+async fn grant_branch(root: Uuid, username: String) -> Result<(), ServerFnError> {
+    use crate::db::{users, Pool, Postgres};
+    crate::auth::require_branch_admin(root).await?;
+    let pool = expect_context::<Pool<Postgres>>();
+    let Ok(user) = users::find(&pool, username.trim()).await else {
+        return Err(ServerFnError::ServerError("error reading user".to_string()));
+    };
+    let Some(user) = user else {
+        return Err(ServerFnError::ServerError("user not found".to_string()));
+    };
+    if user.role == users::Role::Viewer {
+        return Err(ServerFnError::ServerError(VIEWER_GRANT.to_string()));
+    }
+    let Ok(_) = users::grant(&pool, user.id, root).await else {
+        return Err(ServerFnError::ServerError(
+            "error granting branch".to_string(),
+        ));
+    };
+    Ok(())
+}
+
+#[server(encoding = "Cbor")]
+async fn revoke_branch(root: Uuid, user: Uuid) -> Result<(), ServerFnError> {
+    use crate::db::{users, Pool, Postgres};
+    crate::auth::require_branch_admin(root).await?;
+    let pool = expect_context::<Pool<Postgres>>();
+    let Ok(_) = users::revoke(&pool, user, root).await else {
+        return Err(ServerFnError::ServerError(
+            "error revoking branch".to_string(),
+        ));
+    };
+    Ok(())
+}
+
+#[component]
+pub fn Permissions() -> impl IntoView {
+    let params = use_params_map();
+    let root = move || {
+        let id = params.with(|x| x.get("id").cloned()).unwrap();
+        Uuid::from_str(&id).unwrap()
+    };
+
+    let grants = Resource::new(root, get_grants);
+    let error = RwSignal::new(None::<String>);
+    let username_ref = create_node_ref::<html::Input>();
+
+    let add = move |_| {
+        let username = username_ref.get().unwrap().value();
+        spawn_local(async move {
+            match grant_branch(root(), username).await {
+                Ok(_) => {
+                    error.set(None);
+                    grants.refetch();
+                }
+                Err(ServerFnError::ServerError(message)) if message == VIEWER_GRANT => {
+                    error.set(Some(message))
+                }
+                Err(_) => error.set(Some("تعذر منح الصلاحية".to_string())),
+            }
+        });
+    };
+    let remove = move |user: Uuid| {
+        spawn_local(async move {
+            if revoke_branch(root(), user).await.is_ok() {
+                grants.refetch();
+            }
+        });
+    };
+
+    view! {
+        <section class="grid justify-items-center gap-5 overflow-auto">
+            <h1 class="text-center m-5 text-3xl">صلاحيات الفرع</h1>
+            <Transition>
+                {move || {
+                    grants
+                        .get()
+                        .map(|grants| match grants {
+                            Ok((chain, users)) => {
+                                view! {
+                                    <p class="text-center text-2xl">
+                                        <Lineage chain/>
+                                    </p>
+                                    <ul class="grid gap-3">
+                                        {users
+                                            .into_iter()
+                                            .map(|user| {
+                                                let id = user.id;
+                                                view! {
+                                                    <li class="flex flex-row gap-3 items-center">
+                                                        <span>{user.username}</span>
+                                                        <span class="text-gray-300">{user.role.label()}</span>
+                                                        <button
+                                                            on:click=move |_| remove(id)
+                                                            class="px-3 rounded-lg border-2 border-red-600 bg-red-950 hover:border-red-950"
+                                                        >
+                                                            سحب
+                                                        </button>
+                                                    </li>
+                                                }
+                                            })
+                                            .collect_view()}
+                                    </ul>
+                                }
+                                    .into_view()
+                            }
+                            Err(_) => view! { <p>"لا تملك صلاحية ادارة هذا الفرع"</p> }.into_view(),
+                        })
+                }}
+            </Transition>
+            <div class="flex flex-row gap-3">
+                <input
+                    class="placeholder:text-center placeholder-gray-400 bg-gray-800 border-gray-500 hover:border-gray-800 text-center border-2 p-2 text-2xl rounded-lg w-72"
+                    placeholder="اسم المستخدم"
+                    node_ref=username_ref
+                />
+                <button
+                    on:click=add
+                    class="p-3 border-2 border-green-600 bg-green-950 hover:border-green-950 rounded-lg"
+                >
+                    منح الصلاحية
+                </button>
+            </div>
+            <p class="text-red-300">{move || error.get()}</p>
+        </section>
+    }
+}
@@ -0,0 +1,246 @@
+use crate::db::member::SonlessRawMember;
+use chrono::NaiveDateTime;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProposalView {
+    id: Uuid,
+    author: String,
+    created_at: NaiveDateTime,
+    added: Vec<(String, String)>,
+    removed: Vec<String>,
+    changed: Vec<(String, SonlessRawMember)>,
+    moved: Vec<(String, String)>,
+    marriages: usize,
+}
+
+#[cfg(feature = "ssr")]
+const MISSING_MEMBER: &str = "فرد لم يعد موجودا";
+
+// the nasab of a member, or the given name when it was trashed, merged or purged since
+#[cfg(feature = "ssr")]
+async fn nasab(
+    pool: &crate::db::Pool<crate::db::Postgres>,
+    id: Uuid,
+    name: Option<&str>,
+) -> String {
+    use super::nasab::nasab_string;
+    match crate::db::member::ancestors(pool, id).await {
+        Ok(chain) => nasab_string(&chain),
+        Err(_) => name.unwrap_or(MISSING_MEMBER).to_string(),
+    }
+}
+
+#[cfg(feature = "ssr")]
+async fn proposal_view(
+    pool: &crate::db::Pool<crate::db::Postgres>,
+    proposal: crate::db::proposals::Proposal<super::member::ServerUpdates>,
+) -> ProposalView {
+    use crate::db::member::RawMember;
+    fn describe(member: &RawMember) -> String {
+        if member.sons.is_empty() {
+            return member.name.clone();
+        }
+        let sons = member.sons.iter().map(describe).collect::<Vec<_>>();
+        format!("{} ({})", member.name, sons.join("، "))
+    }
+    let updates = proposal.updates;
+    let mut added = Vec::new();
+    for (parent_id, member) in updates.created_members.iter() {
+        added.push((nasab(pool, *parent_id, None).await, describe(member)));
+    }
+    let mut removed = Vec::new();
+    for (id, _) in updates.deleted_members.iter() {
+        removed.push(nasab(pool, *id, None).await);
+    }
+    let mut changed = Vec::new();
+    for member in updates.updated_members {
+        changed.push((nasab(pool, member.id, Some(&member.name)).await, member));
+    }
+    let mut moved = Vec::new();
+    for member in updates.moved_members.iter() {
+        moved.push((
+            nasab(pool, member.id, None).await,
+            nasab(pool, member.parent_id, None).await,
+        ));
+    }
+    ProposalView {
+        id: proposal.id,
+        author: proposal.author,
+        created_at: proposal.created_at,
+        added,
+        removed,
+        changed,
+        moved,
+        marriages: updates.created_marriages.len()
+            + updates.updated_marriages.len()
+            + updates.deleted_marriages.len(),
+    }
+}
+
+#[server(encoding = "Cbor")]
+async fn get_proposals() -> Result<Vec<ProposalView>, ServerFnError> {
+    use super::member::{reviewable, ServerUpdates};
+    use crate::db::{proposals, Pool, Postgres};
+    let user = crate::auth::require_user().await?;
+    let pool = expect_context::<Pool<Postgres>>();
+    let Ok(pending) = proposals::pending::<ServerUpdates>(&pool).await else {
+        return Err(ServerFnError::ServerError(
+            "error reading proposals".to_string(),
+        ));
+    };
+    let mut views = Vec::new();
+    for proposal in pending {
+        if !reviewable(&user, &proposal.updates).await? {
+            continue;
+        }
+        views.push(proposal_view(&pool, proposal).await);
+    }
+    Ok(views)
+}
+
+#[server(encoding = "Cbor")]
+async fn review_proposal(id: Uuid, approve: bool) -> Result<(), ServerFnError> {
+    use super::member::{apply, reviewable, ServerUpdates};
+    use crate::db::{proposals, proposals::ProposalStatus, Pool, Postgres};
+    let user = crate::auth::require_user().await?;
+    let pool = expect_context::<Pool<Postgres>>();
+    let Ok(proposal) = proposals::read::<ServerUpdates>(&pool, id).await else {
+        return Err(ServerFnError::ServerError(
+            "error reading proposal".to_string(),
+        ));
+    };
+    if proposal.status != ProposalStatus::Pending {
+        return Err(ServerFnError::ServerError(
+            "proposal already reviewed".to_string(),
+        ));
+    }
+    if !reviewable(&user, &proposal.updates).await? {
+        return Err(ServerFnError::ServerError("permission denied".to_string()));
+    }
+    let mut transaction = pool.begin().await?;
+    let status = if approve {
+        let conflicts = apply(&mut transaction, proposal.updates, proposal.author_id).await?;
+        if !conflicts.is_empty() {
+            return Err(ServerFnError::ServerError(
+                "proposal conflicts with newer changes".to_string(),
+            ));
+        }
+        ProposalStatus::Approved
+    } else {
+        ProposalStatus::Rejected
+    };
+    let Ok(_) = proposals::review(&mut transaction, id, status, user.id).await else {
+        return Err(ServerFnError::ServerError(
+            "error reviewing proposal".to_string(),
+        ));
+    };
+    transaction.commit().await?;
+    Ok(())
+}
+
+#[component]
+pub fn Proposals() -> impl IntoView {
+    let proposals = Resource::once(get_proposals);
+    let error = RwSignal::new(None::<String>);
+    let review = move |id: Uuid, approve: bool| {
+        spawn_local(async move {
+            match review_proposal(id, approve).await {
+                Ok(_) => {
+                    error.set(None);
+                    proposals.refetch();
+                }
+                Err(_) => error.set(Some("تعذر تطبيق الاقتراح".to_string())),
+            }
+        });
+    };
+
+    view! {
+        <section class="grid justify-items-center gap-5 overflow-auto">
+            <h1 class="text-center m-5 text-3xl">مراجعة الاقتراحات</h1>
+            <p class="text-red-300">{move || error.get()}</p>
+            <Transition>
+                {move || {
+                    proposals
+                        .get()
+                        .map(|proposals| match proposals {
+                            Ok(proposals) if proposals.is_empty() => {
+                                view! { <p>"لا توجد اقتراحات"</p> }.into_view()
+                            }
+                            Ok(proposals) => {
+                                proposals
+                                    .into_iter()
+                                    .map(|proposal| view! { <ProposalCard proposal review/> })
+                                    .collect_view()
+                            }
+                            Err(_) => view! { <p>"يجب تسجيل الدخول"</p> }.into_view(),
+                        })
+                }}
+            </Transition>
+        </section>
+    }
+}
+
+#[component]
+fn ProposalCard<F>(proposal: ProposalView, review: F) -> impl IntoView
+where
+    F: Fn(Uuid, bool) + Copy + 'static,
+{
+    let ProposalView {
+        id,
+        author,
+        created_at,
+        added,
+        removed,
+        changed,
+        moved,
+        marriages,
+    } = proposal;
+
+    view! {
+        <div class="grid gap-2 p-5 w-full max-w-3xl rounded-lg bg-gray-700 border-2 border-gray-500">
+            <p class="text-gray-300">{author}" - "{created_at.format("%Y-%m-%d %H:%M").to_string()}</p>
+            <ul>
+                {added
+                    .into_iter()
+                    .map(|(parent, names)| {
+                        view! { <li class="text-green-300">"+ "{names}" ← "{parent}</li> }
+                    })
+                    .collect_view()}
+                {removed
+                    .into_iter()
+                    .map(|nasab| view! { <li class="text-red-300">"- "{nasab}</li> })
+                    .collect_view()}
+                {changed
+                    .into_iter()
+                    .map(|(old, member)| {
+                        view! { <li class="text-yellow-300">"~ "{old}" ← "{member.name}</li> }
+                    })
+                    .collect_view()}
+                {moved
+                    .into_iter()
+                    .map(|(member, parent)| {
+                        view! { <li class="text-blue-300">"> "{member}" ← "{parent}</li> }
+                    })
+                    .collect_view()}
+                {(marriages > 0).then(|| view! { <li>"تعديلات زواج: "{marriages}</li> })}
+            </ul>
+            <div class="flex flex-row gap-3">
+                <button
+                    on:click=move |_| review(id, true)
+                    class="p-2 w-32 border-2 border-green-600 bg-green-950 hover:border-green-950 rounded-lg"
+                >
+                    قبول
+                </button>
+                <button
+                    on:click=move |_| review(id, false)
+                    class="p-2 w-32 border-2 border-red-600 bg-red-950 hover:border-red-950 rounded-lg"
+                >
+                    رفض
+                </button>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,209 @@
+use std::str::FromStr;
+
+use super::search::MemberSearch;
+use crate::db::member::SonlessRawMember;
+use leptos::*;
+use leptos_router::{use_navigate, use_query_map, A};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Relationship {
+    pub first: SonlessRawMember,
+    pub second: SonlessRawMember,
+    pub common_ancestor: SonlessRawMember,
+    pub first_distance: usize,
+    pub second_distance: usize,
+    pub term: String,
+}
+
+#[server(encoding = "Cbor")]
+async fn get_relationship(
+    first: Uuid,
+    second: Uuid,
+) -> Result<Option<Relationship>, ServerFnError> {
+    use crate::db::{Pool, Postgres};
+    let pool = expect_context::<Pool<Postgres>>();
+    let Ok(first_chains) = lineages(&pool, first).await else {
+        return Err(ServerFnError::ServerError(
+            "first member not found".to_string(),
+        ));
+    };
+    let Ok(second_chains) = lineages(&pool, second).await else {
+        return Err(ServerFnError::ServerError(
+            "second member not found".to_string(),
+        ));
+    };
+    let closest = first_chains
+        .iter()
+        .flat_map(|first| second_chains.iter().map(move |second| (first, second)))
+        .filter_map(|(first, second)| kinship(first, second))
+        .min_by_key(|x| x.first_distance + x.second_distance);
+    Ok(closest)
+}
+
+// the paternal chain of the member, plus the chain through the mother when she is known
+#[cfg(feature = "ssr")]
+async fn lineages(
+    pool: &crate::db::Pool<crate::db::Postgres>,
+    id: Uuid,
+) -> anyhow::Result<Vec<Vec<SonlessRawMember>>> {
+    use crate::db::member::ancestors;
+    let paternal = ancestors(pool, id).await?;
+    let Some(mother_id) = paternal[0].mother_id else {
+        return Ok(vec![paternal]);
+    };
+    let maternal = std::iter::once(paternal[0].clone())
+        .chain(ancestors(pool, mother_id).await?)
+        .collect();
+    Ok(vec![paternal, maternal])
+}
+
+#[cfg(feature = "ssr")]
+fn kinship(
+    first_chain: &[SonlessRawMember],
+    second_chain: &[SonlessRawMember],
+) -> Option<Relationship> {
+    let (first_distance, second_distance) =
+        first_chain.iter().enumerate().find_map(|(i, ancestor)| {
+            second_chain
+                .iter()
+                .position(|x| x.id == ancestor.id)
+                .map(|j| (i, j))
+        })?;
+    Some(Relationship {
+        first: first_chain[0].clone(),
+        second: second_chain[0].clone(),
+        common_ancestor: first_chain[first_distance].clone(),
+        first_distance,
+        second_distance,
+        term: term(first_chain, second_chain, first_distance, second_distance),
+    })
+}
+
+// describes what the second member is to the first one
+#[cfg(feature = "ssr")]
+fn term(
+    first_chain: &[SonlessRawMember],
+    second_chain: &[SonlessRawMember],
+    first_distance: usize,
+    second_distance: usize,
+) -> String {
+    let gendered = |male: &str, female: &str| {
+        if second_chain[0].is_male {
+            male.to_string()
+        } else {
+            female.to_string()
+        }
+    };
+    let child = || gendered("ابن", "ابنة");
+    let sibling = |member: &SonlessRawMember| if member.is_male { "أخ" } else { "أخت" };
+    let uncle =
+        |parent: &SonlessRawMember, uncle: &SonlessRawMember| match (parent.is_male, uncle.is_male)
+        {
+            (true, true) => "عم",
+            (true, false) => "عمة",
+            (false, true) => "خال",
+            (false, false) => "خالة",
+        };
+    match (first_distance, second_distance) {
+        (0, 0) => "نفس الشخص".to_string(),
+        (0, 1) => child(),
+        (0, 2) => gendered("حفيد", "حفيدة"),
+        (0, n) => format!("{} من الجيل {n}", gendered("حفيد", "حفيدة")),
+        (1, 0) => gendered("أب", "أم"),
+        (2, 0) => gendered("جد", "جدة"),
+        (n, 0) => format!("{} من الجيل {n}", gendered("جد", "جدة")),
+        (1, 1) => gendered("أخ", "أخت"),
+        (2, 1) => uncle(&first_chain[1], &second_chain[0]).to_string(),
+        (1, 2) => format!("{} {}", child(), sibling(&second_chain[1])),
+        (2, 2) => format!("{} {}", child(), uncle(&first_chain[1], &second_chain[1])),
+        _ => gendered("قريب", "قريبة"),
+    }
+}
+
+#[component]
+pub fn Relation() -> impl IntoView {
+    let query = use_query_map();
+    let member = move |key: &str| query.with(|x| x.get(key).and_then(|id| Uuid::from_str(id).ok()));
+
+    let navigate = use_navigate();
+    let pick = move |key: &'static str| {
+        let navigate = navigate.clone();
+        Callback::new(move |picked: SonlessRawMember| {
+            let (first, second) = match key {
+                "first" => (Some(picked.id), member("second")),
+                _ => (member("first"), Some(picked.id)),
+            };
+            let query = [("first", first), ("second", second)]
+                .into_iter()
+                .filter_map(|(key, id)| id.map(|id| format!("{key}={id}")))
+                .collect::<Vec<_>>()
+                .join("&");
+            navigate(&format!("/relation?{query}"), Default::default());
+        })
+    };
+
+    let relationship = Resource::new(
+        move || (member("first"), member("second")),
+        |ids| async move {
+            match ids {
+                (Some(first), Some(second)) => get_relationship(first, second).await,
+                _ => Ok(None),
+            }
+        },
+    );
+
+    view! {
+        <section class="grid justify-items-center overflow-auto">
+            <h1 class="text-center m-5 text-3xl">صلة القرابة</h1>
+            <div class="flex flex-row flex-wrap justify-center">
+                <MemberSearch placeholder="الشخص الاول" on_pick=pick("first")/>
+                <MemberSearch placeholder="الشخص الثاني" on_pick=pick("second")/>
+            </div>
+            <Suspense>
+                {move || {
+                    relationship
+                        .get()
+                        .map(|relationship| match relationship {
+                            Ok(Some(relationship)) => view! { <RelationshipView relationship/> },
+                            Ok(None) => view! { <p class="m-5 text-2xl">لا توجد صلة قرابة</p> }.into_view(),
+                            Err(err) => view! { <p class="m-5 text-2xl">{err.to_string()}</p> }.into_view(),
+                        })
+                }}
+            </Suspense>
+        </section>
+    }
+}
+
+#[component]
+fn RelationshipView(relationship: Relationship) -> impl IntoView {
+    let Relationship {
+        first,
+        second,
+        common_ancestor,
+        first_distance,
+        second_distance,
+        term,
+    } = relationship;
+    let generations = first_distance as i64 - second_distance as i64;
+    let pronoun = if second.is_male { " هو " } else { " هي " };
+    view! {
+        <div class="grid justify-items-center gap-3 m-5 text-2xl">
+            <p>
+                <A href=format!("/exists/{}", second.id) class="hover:underline">{second.name}</A>
+                {pronoun}
+                <span class="text-3xl">{term}</span>
+                " "
+                <A href=format!("/exists/{}", first.id) class="hover:underline">{first.name}</A>
+            </p>
+            <p>
+                "الجد المشترك: "
+                <A href=format!("/nasab/{}", common_ancestor.id) class="hover:underline">
+                    {common_ancestor.name}
+                </A>
+            </p>
+            <p>{format!("فرق الاجيال: {generations}")}</p>
+        </div>
+    }
+}
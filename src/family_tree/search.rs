@@ -0,0 +1,75 @@
+use crate::db::member::SonlessRawMember;
+use leptos::*;
+
+use super::nasab::{nasab_string, Lineage};
+
+#[cfg(feature = "ssr")]
+const SEARCH_LIMIT: i64 = 20;
+
+#[server(encoding = "Cbor")]
+async fn search_members(name: String) -> Result<Vec<Vec<SonlessRawMember>>, ServerFnError> {
+    use crate::db::{member::search, Pool, Postgres};
+    let pool = expect_context::<Pool<Postgres>>();
+    match search(&pool, &name, SEARCH_LIMIT).await {
+        Ok(hits) => Ok(hits),
+        Err(err) => Err(ServerFnError::ServerError(err.to_string())),
+    }
+}
+
+#[component]
+pub fn MemberSearch(
+    #[prop(optional, into)] placeholder: Option<String>,
+    #[prop(optional, into)] on_pick: Option<Callback<SonlessRawMember>>,
+) -> impl IntoView {
+    let name = RwSignal::new(String::new());
+    let hits = Resource::new(
+        move || name.get(),
+        |name| async move {
+            if name.trim().chars().count() < 2 {
+                return Ok(Vec::new());
+            }
+            search_members(name).await
+        },
+    );
+
+    let on_input = move |ev| name.set(event_target_value(&ev));
+
+    view! {
+        <div class="grid justify-items-center gap-3 m-5">
+            <input
+                on:input=on_input
+                prop:value=move || name.get()
+                class="placeholder:text-center placeholder-gray-400 bg-gray-800 border-gray-500 hover:border-gray-800 text-center border-2 p-2 text-2xl rounded-lg w-96"
+                placeholder=placeholder.unwrap_or_else(|| "بحث بالاسم".to_string())
+            />
+            <Transition>
+                <ul class="grid gap-2 text-xl">
+                    {move || {
+                        hits.get()
+                            .and_then(Result::ok)
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(|chain| match on_pick {
+                                Some(on_pick) => {
+                                    let member = chain[0].clone();
+                                    let pick = move |_| {
+                                        on_pick.call(member.clone());
+                                        name.set(String::new());
+                                    };
+                                    view! {
+                                        <li>
+                                            <button on:click=pick class="hover:underline">
+                                                {nasab_string(&chain)}
+                                            </button>
+                                        </li>
+                                    }
+                                }
+                                None => view! { <li><Lineage chain/></li> },
+                            })
+                            .collect_view()
+                    }}
+                </ul>
+            </Transition>
+        </div>
+    }
+}
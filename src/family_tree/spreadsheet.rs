@@ -0,0 +1,676 @@
+use super::{import::PreviewMember, member::Committed, search::MemberSearch};
+#[cfg(feature = "ssr")]
+use crate::calendar::{DatePrecision, MemberDate};
+use crate::db::member::{RawMember, SonlessRawMember};
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "ssr")]
+const ID: usize = 0;
+const NAME: usize = 1;
+#[cfg(feature = "ssr")]
+const GENDER: usize = 2;
+#[cfg(feature = "ssr")]
+const FATHER: usize = 3;
+#[cfg(feature = "ssr")]
+const BIRTH: usize = 4;
+#[cfg(feature = "ssr")]
+const DEATH: usize = 5;
+const FIELDS: [&str; 6] = ["المعرف", "الاسم", "الجنس", "الاب", "الميلاد", "الوفاة"];
+// other headers a column may carry, the exported ones among them
+const ALIASES: [&[&str]; 6] = [
+    &["id"],
+    &["name"],
+    &["gender", "sex"],
+    &["معرف الاب", "father", "father id", "father_id"],
+    &["birth"],
+    &["death"],
+];
+#[cfg(feature = "ssr")]
+const HEADERS: [&str; 7] = [
+    "المعرف",
+    "الاسم",
+    "الجنس",
+    "معرف الاب",
+    "الميلاد",
+    "الوفاة",
+    "النسب",
+];
+#[cfg(feature = "ssr")]
+const MALE: [&str; 5] = ["ذكر", "m", "male", "1", "true"];
+#[cfg(feature = "ssr")]
+const FEMALE: [&str; 6] = ["انثى", "انثي", "أنثى", "f", "female", "0"];
+
+// the column read for each field, if any
+type Mapping = [Option<usize>; FIELDS.len()];
+// the headers and the rows of a file
+type Table = (Vec<String>, Vec<Vec<String>>);
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RowError {
+    line: usize,
+    message: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Checked {
+    created: Vec<(Uuid, RawMember)>,
+    errors: Vec<RowError>,
+}
+
+fn guess(headers: &[String]) -> Mapping {
+    let mut mapping = Mapping::default();
+    for (field, column) in mapping.iter_mut().enumerate() {
+        *column = headers.iter().position(|x| {
+            let x = x.trim().to_lowercase();
+            x == FIELDS[field] || ALIASES[field].contains(&x.as_str())
+        });
+    }
+    mapping
+}
+
+fn read(text: &str) -> Result<Table, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(text.trim_start_matches('\u{feff}').as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|x| x.to_string())?
+        .iter()
+        .map(str::to_string)
+        .collect();
+    let rows = reader
+        .records()
+        .map(|x| x.map(|x| x.iter().map(str::to_string).collect()))
+        .collect::<Result<_, _>>()
+        .map_err(|x| x.to_string())?;
+    Ok((headers, rows))
+}
+
+// dates are written 1900-01-31, or as a year prefixed by ~ < > when approximate,
+// before or after it
+#[cfg(feature = "ssr")]
+fn parse_date(text: &str) -> Option<MemberDate> {
+    use chrono::NaiveDate;
+    let (precision, year) = match text.chars().next()? {
+        '~' => (DatePrecision::Approximate, &text[1..]),
+        '<' => (DatePrecision::Before, &text[1..]),
+        '>' => (DatePrecision::After, &text[1..]),
+        _ => match NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+            Ok(date) => {
+                return Some(MemberDate {
+                    date,
+                    precision: DatePrecision::Exact,
+                })
+            }
+            Err(_) => (DatePrecision::Year, text),
+        },
+    };
+    let year = year.trim().parse().ok()?;
+    Some(MemberDate {
+        date: NaiveDate::from_ymd_opt(year, 1, 1)?,
+        precision,
+    })
+}
+
+#[cfg(feature = "ssr")]
+fn format_date(date: &MemberDate) -> String {
+    use chrono::Datelike;
+    let year = date.date.year();
+    match date.precision {
+        DatePrecision::Exact => date.date.format("%Y-%m-%d").to_string(),
+        DatePrecision::Year => year.to_string(),
+        DatePrecision::Approximate => format!("~{year}"),
+        DatePrecision::Before => format!("<{year}"),
+        DatePrecision::After => format!(">{year}"),
+    }
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Father {
+    Member(Uuid),
+    Row(usize),
+}
+
+// builds the members out of the rows, the ones without a father going under the
+// given parent, and reports every row that cannot be inserted as it is
+#[cfg(feature = "ssr")]
+async fn check(
+    pool: &crate::db::Pool<crate::db::Postgres>,
+    parent_id: Uuid,
+    rows: &[Vec<String>],
+    mapping: &Mapping,
+) -> anyhow::Result<Checked> {
+    use crate::db::member;
+    use std::collections::{HashMap, HashSet};
+    let cell = |row: &Vec<String>, field: usize| {
+        mapping[field]
+            .and_then(|x| row.get(x))
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty())
+    };
+    let blank = rows
+        .iter()
+        .map(|row| row.iter().all(|x| x.trim().is_empty()))
+        .collect::<Vec<_>>();
+    let mut errors = Vec::new();
+
+    let mut members = Vec::new();
+    for (index, row) in rows.iter().enumerate() {
+        let name = cell(row, NAME).unwrap_or_default();
+        if name.is_empty() && !blank[index] {
+            errors.push((index, "الاسم فارغ".to_string()));
+        }
+        if name.chars().count() > member::NAME_LENGTH {
+            errors.push((index, format!("الاسم اطول من {} حرفا", member::NAME_LENGTH)));
+        }
+        let is_male = match cell(row, GENDER).map(|x| x.to_lowercase()) {
+            None => true,
+            Some(x) if MALE.contains(&x.as_str()) => true,
+            Some(x) if FEMALE.contains(&x.as_str()) => false,
+            Some(_) => {
+                errors.push((index, "الجنس غير معروف".to_string()));
+                true
+            }
+        };
+        let [birth, death] = [BIRTH, DEATH].map(|field| cell(row, field).map(|x| parse_date(&x)));
+        if matches!(birth, Some(None)) || matches!(death, Some(None)) {
+            errors.push((index, "تاريخ غير صالح".to_string()));
+        }
+        members.push(SonlessRawMember {
+            id: Uuid::new_v4(),
+            name,
+            is_male,
+            birth: birth.flatten(),
+            death: death.flatten(),
+            ..Default::default()
+        });
+    }
+
+    let mut keys = HashMap::new();
+    for (index, row) in rows.iter().enumerate() {
+        if let Some(key) = cell(row, ID) {
+            if keys.insert(key, index).is_some() {
+                errors.push((index, "المعرف مكرر".to_string()));
+            }
+        }
+    }
+    let fathers = rows.iter().map(|row| cell(row, FATHER)).collect::<Vec<_>>();
+    let outside = fathers
+        .iter()
+        .flatten()
+        .filter(|x| !keys.contains_key(*x))
+        .collect::<Vec<_>>();
+    let ids = outside
+        .iter()
+        .filter_map(|x| x.parse().ok())
+        .chain([parent_id])
+        .collect::<Vec<_>>();
+    let found = member::read_sonless(pool, &ids)
+        .await?
+        .into_iter()
+        .map(|x| (x.id, x.is_male))
+        .collect::<HashMap<_, _>>();
+    if !found.contains_key(&parent_id) {
+        anyhow::bail!("member {parent_id} not found");
+    }
+    let names = outside
+        .iter()
+        .filter(|x| x.parse::<Uuid>().is_err())
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>();
+    let mut named = HashMap::<String, Vec<Uuid>>::new();
+    for (id, name) in member::named(pool, &names).await? {
+        named.entry(name).or_default().push(id);
+    }
+    let mut in_file = HashMap::<&str, Vec<usize>>::new();
+    for (index, member) in members.iter().enumerate() {
+        in_file.entry(&member.name).or_default().push(index);
+    }
+
+    let mut resolved = Vec::new();
+    for (index, father) in fathers.iter().enumerate() {
+        let father = match father {
+            _ if blank[index] => Err(None),
+            None => Ok(Father::Member(parent_id)),
+            Some(x) => match (keys.get(x), x.parse::<Uuid>()) {
+                (Some(row), _) => Ok(Father::Row(*row)),
+                (None, Ok(id)) => match found.get(&id) {
+                    Some(true) => Ok(Father::Member(id)),
+                    Some(false) => Err(Some("الاب انثى")),
+                    None => Err(Some("الاب غير موجود")),
+                },
+                (None, Err(_)) => {
+                    match (
+                        in_file.get(x.as_str()).map(Vec::as_slice),
+                        named.get(x).map(Vec::as_slice),
+                    ) {
+                        (Some([row]), _) => Ok(Father::Row(*row)),
+                        (Some(_), _) | (None, Some([_, _, ..])) => {
+                            Err(Some("اسم الاب يطابق اكثر من فرد، استعمل المعرف"))
+                        }
+                        (None, Some([id])) => Ok(Father::Member(*id)),
+                        _ => Err(Some("الاب غير موجود")),
+                    }
+                }
+            },
+        };
+        let father = match father {
+            Ok(Father::Row(row)) if !members[row].is_male => Err(Some("الاب انثى")),
+            father => father,
+        };
+        if let Err(Some(message)) = father {
+            errors.push((index, message.to_string()));
+        }
+        resolved.push(father.ok());
+    }
+
+    let mut sons = HashMap::<usize, Vec<usize>>::new();
+    let mut roots = Vec::new();
+    for (index, father) in resolved.iter().enumerate() {
+        match father {
+            Some(Father::Row(row)) => sons.entry(*row).or_default().push(index),
+            Some(Father::Member(id)) => roots.push((*id, index)),
+            None => (),
+        }
+    }
+    let mut placed = vec![false; rows.len()];
+    let mut stack = roots.iter().map(|(_, x)| *x).collect::<Vec<_>>();
+    while let Some(index) = stack.pop() {
+        placed[index] = true;
+        stack.extend(sons.get(&index).into_iter().flatten());
+    }
+    for (index, father) in resolved.iter().enumerate() {
+        if father.is_some() && !placed[index] {
+            errors.push((index, "الفرد من اجداد نفسه".to_string()));
+        }
+    }
+
+    let parents = roots.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+    let taken = member::son_names(pool, &parents)
+        .await?
+        .into_iter()
+        .collect::<HashSet<_>>();
+    let mut seen = HashMap::new();
+    for (index, father) in resolved.iter().enumerate() {
+        let (Some(father), name) = (father, &members[index].name) else {
+            continue;
+        };
+        if let Some(first) = seen.insert((*father, name), index) {
+            errors.push((
+                index,
+                format!("الاسم مكرر تحت نفس الاب في السطر {}", first + 2),
+            ));
+        }
+        if let Father::Member(id) = father {
+            if taken.contains(&(*id, name.clone())) {
+                errors.push((index, "يوجد فرد بنفس الاسم تحت الاب".to_string()));
+            }
+        }
+    }
+
+    fn build(
+        index: usize,
+        members: &[SonlessRawMember],
+        sons: &HashMap<usize, Vec<usize>>,
+    ) -> RawMember {
+        let sons = sons
+            .get(&index)
+            .into_iter()
+            .flatten()
+            .map(|x| build(*x, members, sons))
+            .collect();
+        members[index].clone().with_sons(sons, false)
+    }
+    errors.sort_by_key(|(index, _)| *index);
+    let created = match errors.is_empty() {
+        true => roots
+            .into_iter()
+            .map(|(id, index)| (id, build(index, &members, &sons)))
+            .collect(),
+        false => Vec::new(),
+    };
+    Ok(Checked {
+        created,
+        errors: errors
+            .into_iter()
+            .map(|(index, message)| RowError {
+                line: index + 2,
+                message,
+            })
+            .collect(),
+    })
+}
+
+#[server(encoding = "Cbor")]
+async fn check_rows(
+    parent_id: Uuid,
+    rows: Vec<Vec<String>>,
+    mapping: Mapping,
+) -> Result<Checked, ServerFnError> {
+    use crate::db::{Pool, Postgres};
+    crate::auth::require_user().await?;
+    let pool = expect_context::<Pool<Postgres>>();
+    let Ok(checked) = check(&pool, parent_id, &rows, &mapping).await else {
+        return Err(ServerFnError::ServerError(
+            "error checking rows".to_string(),
+        ));
+    };
+    Ok(checked)
+}
+
+#[server(encoding = "Cbor")]
+async fn import_rows(
+    parent_id: Uuid,
+    rows: Vec<Vec<String>>,
+    mapping: Mapping,
+) -> Result<Committed, ServerFnError> {
+    use super::member::{submit, ServerUpdates};
+    use crate::db::{Pool, Postgres};
+    let user = crate::auth::require_user().await?;
+    let pool = expect_context::<Pool<Postgres>>();
+    let Ok(checked) = check(&pool, parent_id, &rows, &mapping).await else {
+        return Err(ServerFnError::ServerError(
+            "error checking rows".to_string(),
+        ));
+    };
+    if !checked.errors.is_empty() {
+        return Err(ServerFnError::ServerError("invalid rows".to_string()));
+    }
+    let updates = ServerUpdates {
+        created_members: checked.created,
+        ..Default::default()
+    };
+    submit(&user, updates).await
+}
+
+#[cfg(feature = "ssr")]
+fn sonless(member: &RawMember) -> SonlessRawMember {
+    SonlessRawMember {
+        id: member.id,
+        name: member.name.clone(),
+        is_male: member.is_male,
+        mother_id: member.mother_id,
+        birth: member.birth,
+        death: member.death,
+        names: member.names.clone(),
+        version: member.version,
+    }
+}
+
+// one row per member, fathers before their sons; chain holds the member and its
+// ancestors for the nasab
+#[cfg(feature = "ssr")]
+fn write_rows(
+    writer: &mut csv::Writer<Vec<u8>>,
+    member: &RawMember,
+    father: Option<Uuid>,
+    chain: &[SonlessRawMember],
+) -> csv::Result<()> {
+    let date = |x: &Option<MemberDate>| x.as_ref().map(format_date).unwrap_or_default();
+    writer.write_record([
+        member.id.to_string(),
+        member.name.clone(),
+        if member.is_male { "ذكر" } else { "انثى" }.to_string(),
+        father.map(|x| x.to_string()).unwrap_or_default(),
+        date(&member.birth),
+        date(&member.death),
+        super::nasab::nasab_string(chain),
+    ])?;
+    for son in member.sons.iter() {
+        let chain = [&[sonless(son)], chain].concat();
+        write_rows(writer, son, Some(member.id), &chain)?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn export(
+    axum::Extension(pool): axum::Extension<crate::db::Pool<crate::db::Postgres>>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> axum::response::Response {
+    use crate::db::member;
+    use axum::{
+        http::{header, StatusCode},
+        response::IntoResponse,
+    };
+    let (Ok(root), Ok(chain)) = (
+        member::read(&pool, id, None).await,
+        member::ancestors(&pool, id).await,
+    ) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let father = chain.get(1).map(|x| x.id);
+    let mut writer = csv::Writer::from_writer("\u{feff}".as_bytes().to_vec());
+    let written = writer
+        .write_record(HEADERS)
+        .and_then(|_| write_rows(&mut writer, &super::transfer::sorted(root), father, &chain));
+    let Ok(body) = written
+        .map_err(|_| ())
+        .and_then(|_| writer.into_inner().map_err(|_| ()))
+    else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    (
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{id}.csv\""),
+            ),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+#[component]
+pub fn CsvImport() -> impl IntoView {
+    let table = RwSignal::new(None::<Table>);
+    let mapping = RwSignal::new(Mapping::default());
+    let parent = RwSignal::new(None::<SonlessRawMember>);
+    let checked = RwSignal::new(None::<Checked>);
+    let status = RwSignal::new(None::<String>);
+
+    let on_change = move |ev: ev::Event| {
+        let input = event_target::<web_sys::HtmlInputElement>(&ev);
+        let Some(file) = input.files().and_then(|x| x.get(0)) else {
+            return;
+        };
+        status.set(None);
+        checked.set(None);
+        spawn_local(async move {
+            let text = wasm_bindgen_futures::JsFuture::from(file.text())
+                .await
+                .ok()
+                .and_then(|x| x.as_string());
+            match text
+                .ok_or("تعذرت قراءة الملف".to_string())
+                .and_then(|x| read(&x))
+            {
+                Ok((headers, rows)) => {
+                    mapping.set(guess(&headers));
+                    table.set(Some((headers, rows)));
+                }
+                Err(error) => {
+                    table.set(None);
+                    status.set(Some(error));
+                }
+            }
+        });
+    };
+    let pick = Callback::new(move |member: SonlessRawMember| {
+        parent.set(Some(member));
+        checked.set(None);
+    });
+    let rows = move || {
+        table
+            .get_untracked()
+            .map(|(_, rows)| rows)
+            .unwrap_or_default()
+    };
+    let preview = move |_| {
+        let Some(parent) = parent.get_untracked() else {
+            return;
+        };
+        status.set(None);
+        spawn_local(async move {
+            match check_rows(parent.id, rows(), mapping.get_untracked()).await {
+                Ok(result) => checked.set(Some(result)),
+                Err(_) => status.set(Some("تعذر فحص الملف".to_string())),
+            }
+        });
+    };
+    let commit = move |_| {
+        let Some(parent) = parent.get_untracked() else {
+            return;
+        };
+        spawn_local(async move {
+            let message = match import_rows(parent.id, rows(), mapping.get_untracked()).await {
+                Ok(Committed::Applied) => {
+                    checked.set(None);
+                    "تم الاستيراد".to_string()
+                }
+                Ok(Committed::Proposed) => {
+                    checked.set(None);
+                    "تم ارسال الاستيراد للمراجعة".to_string()
+                }
+                Ok(Committed::Conflicted(found)) => {
+                    let names = found
+                        .into_iter()
+                        .filter_map(|x| x.yours.map(|x| x.name))
+                        .collect::<Vec<_>>();
+                    format!("اضيف افراد بنفس الاسم منذ الفحص: {}", names.join("، "))
+                }
+                Err(_) => "تعذر الاستيراد".to_string(),
+            };
+            status.set(Some(message));
+        });
+    };
+
+    view! {
+        <section class="grid justify-items-center gap-5 overflow-auto">
+            <h1 class="text-center m-5 text-3xl">استيراد جدول</h1>
+            <label class="grid justify-items-center gap-2">
+                "ملف CSV"
+                <input type="file" accept=".csv" on:change=on_change class="text-xl"/>
+            </label>
+            <p>{move || status.get()}</p>
+            <Show when=move || table.get().is_some()>
+                <div class="grid grid-cols-[auto_1fr] gap-x-5 gap-y-2">
+                    {(0..FIELDS.len())
+                        .map(|field| {
+                            view! {
+                                <p class="text-gray-300">{FIELDS[field]}</p>
+                                <ColumnSelect field table mapping checked/>
+                            }
+                        })
+                        .collect_view()}
+                </div>
+                <p class="text-xl">
+                    {move || match parent.get() {
+                        Some(parent) => format!("من لا اب له يضاف تحت: {}", parent.name),
+                        None => "اختر الفرد الذي يضاف تحته من لا اب له".to_string(),
+                    }}
+                </p>
+                <MemberSearch placeholder="بحث عن الاب" on_pick=pick/>
+                <button
+                    on:click=preview
+                    disabled=move || parent.get().is_none() || mapping.get()[NAME].is_none()
+                    class="p-2 w-32 border-2 border-gray-500 bg-gray-800 hover:border-gray-800 rounded-lg"
+                >
+                    فحص
+                </button>
+            </Show>
+            {move || checked.get().map(|checked| view! { <CheckedRows checked/> })}
+            <Show when=move || checked.get().is_some_and(|x| x.errors.is_empty())>
+                <button
+                    on:click=commit
+                    class="p-2 w-32 border-2 border-green-600 bg-green-950 hover:border-green-950 rounded-lg"
+                >
+                    استيراد
+                </button>
+            </Show>
+        </section>
+    }
+}
+
+#[component]
+fn ColumnSelect(
+    field: usize,
+    table: RwSignal<Option<Table>>,
+    mapping: RwSignal<Mapping>,
+    checked: RwSignal<Option<Checked>>,
+) -> impl IntoView {
+    let headers = table
+        .get_untracked()
+        .map(|(headers, _)| headers)
+        .unwrap_or_default();
+    let on_change = move |ev| {
+        let column = event_target_value(&ev).parse().ok();
+        mapping.update(|xs| xs[field] = column);
+        checked.set(None);
+    };
+
+    view! {
+        <select
+            on:change=on_change
+            class="bg-gray-800 border-gray-500 hover:border-gray-800 border-2 rounded-lg"
+        >
+            <option value="" selected=move || mapping.get()[field].is_none()>
+                "-"
+            </option>
+            {headers
+                .into_iter()
+                .enumerate()
+                .map(|(column, header)| {
+                    view! {
+                        <option
+                            value=column.to_string()
+                            selected=move || mapping.get()[field] == Some(column)
+                        >
+                            {header}
+                        </option>
+                    }
+                })
+                .collect_view()}
+        </select>
+    }
+}
+
+#[component]
+fn CheckedRows(checked: Checked) -> impl IntoView {
+    let Checked { created, errors } = checked;
+
+    view! {
+        <div class="grid gap-2 p-5 w-full max-w-3xl rounded-lg bg-gray-700 border-2 border-gray-500">
+            {match errors.is_empty() {
+                true => {
+                    view! {
+                        <ul class="grid gap-1">
+                            {created
+                                .into_iter()
+                                .map(|(_, member)| view! { <PreviewMember member/> })
+                                .collect_view()}
+                        </ul>
+                    }
+                        .into_view()
+                }
+                false => {
+                    view! {
+                        <p class="text-red-300">"لم يستورد شيء، صحح الاسطر التالية:"</p>
+                        <ul class="grid gap-1 text-red-300">
+                            {errors
+                                .into_iter()
+                                .map(|x| view! { <li>"السطر "{x.line}": "{x.message}</li> })
+                                .collect_view()}
+                        </ul>
+                    }
+                        .into_view()
+                }
+            }}
+        </div>
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+
+use super::member::Rm;
+use crate::db::{history::Batch, member, member::RawMember, Pool, Postgres};
+use axum::{
+    body::Bytes,
+    extract::{Path, Query},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+const CBOR: &str = "application/cbor";
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Ids {
+    #[default]
+    Keep,
+    New,
+}
+
+#[derive(Deserialize, Clone, Copy, Default, Debug)]
+pub struct ImportOptions {
+    #[serde(default)]
+    ids: Ids,
+}
+
+// sons ordered by name, which is unique among siblings, so that exporting the same
+// tree always gives the same document
+pub fn sorted(mut member: RawMember) -> RawMember {
+    member.sons.sort_by(|a, b| a.name.cmp(&b.name));
+    member.sons = member.sons.into_iter().map(sorted).collect();
+    member
+}
+
+fn regenerate(member: &mut RawMember, ids: &HashMap<Uuid, Uuid>) {
+    member.id = ids[&member.id];
+    member.mother_id = member.mother_id.map(|x| ids.get(&x).copied().unwrap_or(x));
+    for son in member.sons.iter_mut() {
+        regenerate(son, ids);
+    }
+}
+
+fn clear_mothers(member: &mut RawMember, known: &[Uuid]) {
+    if member.mother_id.is_some_and(|x| !known.contains(&x)) {
+        member.mother_id = None;
+    }
+    for son in member.sons.iter_mut() {
+        clear_mothers(son, known);
+    }
+}
+
+fn mothers(member: &RawMember) -> Vec<Uuid> {
+    member
+        .mother_id
+        .into_iter()
+        .chain(member.sons.iter().flat_map(mothers))
+        .collect()
+}
+
+async fn read(pool: &Pool<Postgres>, id: Uuid) -> Result<RawMember, Response> {
+    match member::read(pool, id, None).await {
+        Ok(member) => Ok(sorted(member)),
+        Err(_) => Err(StatusCode::NOT_FOUND.into_response()),
+    }
+}
+
+pub async fn export_json(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Path(id): Path<Uuid>,
+) -> Response {
+    match read(&pool, id).await {
+        Ok(member) => Json(member).into_response(),
+        Err(response) => response,
+    }
+}
+
+pub async fn export_cbor(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Path(id): Path<Uuid>,
+) -> Response {
+    let member = match read(&pool, id).await {
+        Ok(member) => member,
+        Err(response) => return response,
+    };
+    let mut body = Vec::new();
+    if ciborium::into_writer(&member, &mut body).is_err() {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+    ([(header::CONTENT_TYPE, CBOR)], body).into_response()
+}
+
+// inserts an exported tree under the given parent, with its ids kept unless new ones
+// are asked for; only users who may edit the parent can import, nothing is proposed
+pub async fn import(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Path(parent_id): Path<Uuid>,
+    Query(options): Query<ImportOptions>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let Ok(user) = crate::auth::request_user(&pool, &headers).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let Some(user) = user else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+    let Ok(editable) = crate::auth::editable_in(&pool, &user, vec![parent_id]).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    if !editable.contains(&parent_id) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let is_cbor = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|x| x.to_str().ok())
+        .is_some_and(|x| x.starts_with(CBOR));
+    let parsed = if is_cbor {
+        ciborium::from_reader::<RawMember, _>(body.as_ref()).map_err(|x| x.to_string())
+    } else {
+        serde_json::from_slice::<RawMember>(&body).map_err(|x| x.to_string())
+    };
+    let mut root = match parsed {
+        Ok(root) => root,
+        Err(error) => return (StatusCode::BAD_REQUEST, error).into_response(),
+    };
+
+    if options.ids == Ids::New {
+        let ids = root
+            .ids()
+            .into_iter()
+            .map(|x| (x, Uuid::new_v4()))
+            .collect::<HashMap<_, _>>();
+        regenerate(&mut root, &ids);
+    }
+    let ids = root.ids();
+    let Ok(taken) = member::existing(&pool, &ids).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    if !taken.is_empty() {
+        return (StatusCode::CONFLICT, Json(taken)).into_response();
+    }
+    let Ok(outside) = member::existing(&pool, &mothers(&root)).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    clear_mothers(&mut root, &[ids, outside].concat());
+
+    let Ok(mut transaction) = pool.begin().await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let created = [(parent_id, root)];
+    let Ok(conflicts) = member::conflicts(&mut transaction, &created, &[], &[], &[]).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    if !conflicts.is_empty() {
+        return (StatusCode::CONFLICT, Json(conflicts)).into_response();
+    }
+    let [(_, root)] = created;
+    let id = root.id;
+    let batch = Batch::new(user.id);
+    if member::create(&mut transaction, root, Some(parent_id), &batch)
+        .await
+        .is_err()
+    {
+        return StatusCode::UNPROCESSABLE_ENTITY.into_response();
+    }
+    if transaction.commit().await.is_err() {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+    (StatusCode::CREATED, Json(id)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::{DatePrecision, MemberDate};
+    use crate::db::member::NameParts;
+    use chrono::NaiveDate;
+
+    fn member(name: &str, sons: Vec<RawMember>) -> RawMember {
+        RawMember {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            is_male: true,
+            sons,
+            ..Default::default()
+        }
+    }
+
+    fn tree() -> RawMember {
+        let mut ali = member("علي", vec![member("حسن", vec![])]);
+        ali.mother_id = Some(Uuid::new_v4());
+        ali.birth = Some(MemberDate {
+            date: NaiveDate::from_ymd_opt(1900, 1, 1).unwrap(),
+            precision: DatePrecision::Approximate,
+        });
+        ali.names = NameParts {
+            laqab: Some("الكبير".to_string()),
+            alternate: vec!["ابو الحسن".to_string()],
+            ..Default::default()
+        };
+        let mut root = member("محمد", vec![member("عمر", vec![]), ali]);
+        root.version = 3;
+        root
+    }
+
+    #[test]
+    fn sorted_orders_sons_by_name() {
+        let tree = sorted(tree());
+        let names = tree
+            .sons
+            .iter()
+            .map(|x| x.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["علي", "عمر"]);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let tree = sorted(tree());
+        let json = serde_json::to_vec(&tree).unwrap();
+        assert_eq!(serde_json::from_slice::<RawMember>(&json).unwrap(), tree);
+    }
+
+    #[test]
+    fn cbor_round_trip() {
+        let tree = sorted(tree());
+        let mut cbor = Vec::new();
+        ciborium::into_writer(&tree, &mut cbor).unwrap();
+        assert_eq!(
+            ciborium::from_reader::<RawMember, _>(cbor.as_slice()).unwrap(),
+            tree
+        );
+    }
+
+    #[test]
+    fn regenerate_remaps_mothers_in_the_tree_only() {
+        let mut hind = member("هند", vec![]);
+        hind.is_male = false;
+        let mut zaid = member("زيد", vec![]);
+        zaid.mother_id = Some(hind.id);
+        let outside = Uuid::new_v4();
+        let mut amr = member("عمرو", vec![]);
+        amr.mother_id = Some(outside);
+        let mut tree = member("محمد", vec![hind, zaid, amr]);
+
+        let ids = tree
+            .ids()
+            .into_iter()
+            .map(|x| (x, Uuid::new_v4()))
+            .collect::<HashMap<_, _>>();
+        let hind = tree.sons[0].id;
+        regenerate(&mut tree, &ids);
+
+        assert!(tree.ids().iter().all(|x| ids.values().any(|y| y == x)));
+        assert_eq!(tree.sons[0].id, ids[&hind]);
+        assert_eq!(tree.sons[1].mother_id, Some(ids[&hind]));
+        assert_eq!(tree.sons[2].mother_id, Some(outside));
+    }
+}
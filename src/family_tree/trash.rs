@@ -0,0 +1,193 @@
+use super::nasab::Lineage;
+use crate::db::member::{SonlessRawMember, Trashed};
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+const TRASH_RETENTION_DAYS: i32 = 30;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TrashView {
+    trashed: Trashed,
+    chain: Vec<SonlessRawMember>,
+}
+
+#[cfg(feature = "ssr")]
+pub async fn restore_deletion(
+    user: &crate::db::users::UserInfo,
+    deletion_id: Uuid,
+) -> Result<(), ServerFnError> {
+    use crate::db::{history::Batch, member, Pool, Postgres};
+    let pool = expect_context::<Pool<Postgres>>();
+    let mut transaction = pool.begin().await?;
+    let Ok(Some(parent)) = member::trash_parent(&mut transaction, deletion_id).await else {
+        return Err(ServerFnError::ServerError(
+            "error reading trash".to_string(),
+        ));
+    };
+    if !crate::auth::editable(user, vec![parent])
+        .await?
+        .contains(&parent)
+    {
+        return Err(ServerFnError::ServerError("permission denied".to_string()));
+    }
+    let Ok(_) = member::restore(&mut transaction, deletion_id, &Batch::new(user.id)).await else {
+        return Err(ServerFnError::ServerError(
+            "error restoring member".to_string(),
+        ));
+    };
+    transaction.commit().await?;
+    Ok(())
+}
+
+#[server(encoding = "Cbor")]
+async fn get_trash() -> Result<Vec<TrashView>, ServerFnError> {
+    use crate::db::{member, Pool, Postgres};
+    let user = crate::auth::require_user().await?;
+    let pool = expect_context::<Pool<Postgres>>();
+    let Ok(trash) = member::trash(&pool).await else {
+        return Err(ServerFnError::ServerError(
+            "error reading trash".to_string(),
+        ));
+    };
+    let parents = trash.iter().map(|x| x.parent_id).collect();
+    let editable = crate::auth::editable(&user, parents).await?;
+    let mut views = Vec::new();
+    for trashed in trash {
+        if !editable.contains(&trashed.parent_id) {
+            continue;
+        }
+        let chain = member::ancestors(&pool, trashed.parent_id)
+            .await
+            .unwrap_or_default();
+        views.push(TrashView { trashed, chain });
+    }
+    Ok(views)
+}
+
+#[server(encoding = "Cbor")]
+async fn restore_trash(deletion_id: Uuid) -> Result<(), ServerFnError> {
+    let user = crate::auth::require_user().await?;
+    restore_deletion(&user, deletion_id).await
+}
+
+#[server(encoding = "Cbor")]
+async fn purge_trash(deletion_id: Uuid) -> Result<(), ServerFnError> {
+    use crate::db::{history::Batch, member, Pool, Postgres};
+    crate::auth::require_user().await?;
+    let pool = expect_context::<Pool<Postgres>>();
+    let mut transaction = pool.begin().await?;
+    let Ok(Some(parent)) = member::trash_parent(&mut transaction, deletion_id).await else {
+        return Err(ServerFnError::ServerError(
+            "error reading trash".to_string(),
+        ));
+    };
+    let user = crate::auth::require_branch_admin(parent).await?;
+    let batch = Batch::new(user.id);
+    let Ok(_) = member::purge(&mut transaction, deletion_id, TRASH_RETENTION_DAYS, &batch).await
+    else {
+        return Err(ServerFnError::ServerError(
+            "error purging member".to_string(),
+        ));
+    };
+    transaction.commit().await?;
+    Ok(())
+}
+
+#[component]
+pub fn Trash() -> impl IntoView {
+    let trash = Resource::once(get_trash);
+    let error = RwSignal::new(None::<&'static str>);
+    let restore = move |deletion_id: Uuid| {
+        spawn_local(async move {
+            match restore_trash(deletion_id).await {
+                Ok(_) => {
+                    error.set(None);
+                    trash.refetch();
+                }
+                Err(_) => error.set(Some("تعذرت الاستعادة")),
+            }
+        });
+    };
+    let purge = move |deletion_id: Uuid| {
+        spawn_local(async move {
+            match purge_trash(deletion_id).await {
+                Ok(_) => {
+                    error.set(None);
+                    trash.refetch();
+                }
+                Err(_) => error.set(Some("تعذر الحذف النهائي")),
+            }
+        });
+    };
+
+    view! {
+        <section class="grid justify-items-center gap-5 overflow-auto">
+            <h1 class="text-center m-5 text-3xl">المحذوفات</h1>
+            <p class="text-red-300">{move || error.get()}</p>
+            <Transition>
+                {move || {
+                    trash
+                        .get()
+                        .map(|trash| match trash {
+                            Ok(trash) if trash.is_empty() => {
+                                view! { <p>"لا توجد محذوفات"</p> }.into_view()
+                            }
+                            Ok(trash) => {
+                                trash
+                                    .into_iter()
+                                    .map(|view| view! { <TrashCard view restore purge/> })
+                                    .collect_view()
+                            }
+                            Err(_) => view! { <p>"يجب تسجيل الدخول"</p> }.into_view(),
+                        })
+                }}
+            </Transition>
+        </section>
+    }
+}
+
+#[component]
+fn TrashCard<R, P>(view: TrashView, restore: R, purge: P) -> impl IntoView
+where
+    R: Fn(Uuid) + Copy + 'static,
+    P: Fn(Uuid) + Copy + 'static,
+{
+    let TrashView { trashed, chain } = view;
+    let Trashed {
+        deletion_id,
+        member,
+        deleted_at,
+        deleted_by,
+        members,
+        ..
+    } = trashed;
+    let purgeable_at = deleted_at + chrono::Duration::days(TRASH_RETENTION_DAYS as i64);
+
+    view! {
+        <div class="grid gap-2 p-5 w-full max-w-3xl rounded-lg bg-gray-700 border-2 border-gray-500">
+            <p class="text-xl">{member.name}" ← "<Lineage chain/></p>
+            <p class="text-gray-300">
+                {deleted_by.unwrap_or_default()}" - "
+                {deleted_at.format("%Y-%m-%d %H:%M").to_string()}" - "{members}" فرد"
+            </p>
+            <div class="flex flex-row gap-3 items-center">
+                <button
+                    on:click=move |_| restore(deletion_id)
+                    class="p-2 w-32 border-2 border-green-600 bg-green-950 hover:border-green-950 rounded-lg"
+                >
+                    استعادة
+                </button>
+                <button
+                    on:click=move |_| purge(deletion_id)
+                    class="p-2 w-32 border-2 border-red-600 bg-red-950 hover:border-red-950 rounded-lg"
+                >
+                    حذف نهائي
+                </button>
+                <span class="text-gray-300">
+                    "يمكن الحذف النهائي بعد "{purgeable_at.format("%Y-%m-%d").to_string()}
+                </span>
+            </div>
+        </div>
+    }
+}
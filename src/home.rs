@@ -0,0 +1,153 @@
+use crate::{
+    auth::UserMenu,
+    db::member::{SonlessRawMember, Stats},
+    family_tree::{Lineage, MemberSearch},
+};
+use leptos::*;
+use leptos_router::A;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+const RECENT_DAYS: i32 = 7;
+#[cfg(feature = "ssr")]
+const RECENT_LIMIT: i64 = 10;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Dashboard {
+    stats: Stats,
+    families: Vec<(SonlessRawMember, i64)>,
+    recent: Vec<SonlessRawMember>,
+    me: Option<Vec<SonlessRawMember>>,
+}
+
+#[server(encoding = "Cbor")]
+async fn get_dashboard() -> Result<Dashboard, ServerFnError> {
+    use crate::db::{member, Pool, Postgres};
+    let pool = expect_context::<Pool<Postgres>>();
+    let Ok(stats) = member::stats(&pool, RECENT_DAYS).await else {
+        return Err(ServerFnError::ServerError(
+            "error counting members".to_string(),
+        ));
+    };
+    let Ok(families) = member::families(&pool).await else {
+        return Err(ServerFnError::ServerError(
+            "error reading families".to_string(),
+        ));
+    };
+    let Ok(recent) = member::recent(&pool, RECENT_LIMIT).await else {
+        return Err(ServerFnError::ServerError(
+            "error reading recent members".to_string(),
+        ));
+    };
+    let me = match crate::auth::current_user().await?.and_then(|x| x.member_id) {
+        Some(id) => member::ancestors(&pool, id).await.ok(),
+        None => None,
+    };
+    Ok(Dashboard {
+        stats,
+        families,
+        recent,
+        me,
+    })
+}
+
+#[component]
+pub fn Home() -> impl IntoView {
+    let dashboard = Resource::once(get_dashboard);
+
+    view! {
+        <section class="grid justify-items-center overflow-auto">
+            <UserMenu/>
+            <h1 class="text-center m-5 text-3xl">انساب</h1>
+            <MemberSearch/>
+            <A
+                href="/relation"
+                class="p-3 w-56 text-center border-2 border-gray-400 bg-gray-950 hover:border-gray-950 rounded-lg"
+            >
+                صلة القرابة
+            </A>
+            <Suspense>
+                {move || {
+                    dashboard
+                        .get()
+                        .and_then(Result::ok)
+                        .map(|dashboard| view! { <DashboardView dashboard/> })
+                }}
+            </Suspense>
+        </section>
+    }
+}
+
+#[component]
+fn DashboardView(dashboard: Dashboard) -> impl IntoView {
+    let Dashboard {
+        stats,
+        families,
+        recent,
+        me,
+    } = dashboard;
+
+    let me = me.map(|chain| {
+        let id = chain.first().map(|x| x.id).unwrap_or_default();
+        view! {
+            <div class="grid justify-items-center gap-3 m-5 p-5 rounded-lg border-2 border-gray-400 bg-gray-700">
+                <p class="text-2xl text-center">
+                    <Lineage chain/>
+                </p>
+                <A
+                    href=format!("/exists/{id}")
+                    class="p-3 w-56 text-center border-2 border-gray-400 bg-gray-950 hover:border-gray-950 rounded-lg"
+                >
+                    فرعي في الشجرة
+                </A>
+            </div>
+        }
+    });
+
+    view! {
+        {me}
+        <div class="flex flex-row flex-wrap justify-center gap-5 m-5">
+            <StatCard title="عدد الافراد" value=stats.members/>
+            <StatCard title="عدد الاجيال" value=stats.generations/>
+            <StatCard title="اضيفوا مؤخرا" value=stats.recently_added/>
+        </div>
+        <div class="flex flex-row flex-wrap justify-center gap-10 m-5 text-xl">
+            <div class="grid content-start gap-2">
+                <h2 class="text-center text-2xl">العائلات</h2>
+                {families
+                    .into_iter()
+                    .map(|(family, members)| {
+                        view! {
+                            <A href=format!("/exists/{}", family.id) class="hover:underline">
+                                {format!("{} ({members})", family.name)}
+                            </A>
+                        }
+                    })
+                    .collect_view()}
+            </div>
+            <div class="grid content-start gap-2">
+                <h2 class="text-center text-2xl">اخر الاضافات</h2>
+                {recent
+                    .into_iter()
+                    .map(|member| {
+                        view! {
+                            <A href=format!("/nasab/{}", member.id) class="hover:underline">
+                                {member.name}
+                            </A>
+                        }
+                    })
+                    .collect_view()}
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn StatCard(title: &'static str, value: i64) -> impl IntoView {
+    view! {
+        <div class="grid justify-items-center p-5 w-56 rounded-lg border-2 border-gray-400 bg-gray-700">
+            <span class="text-4xl">{value}</span>
+            <span class="text-xl">{title}</span>
+        </div>
+    }
+}
@@ -1,7 +1,11 @@
+pub mod accounts;
 pub mod app;
+pub mod auth;
+pub mod calendar;
 pub mod db;
 pub mod error_template;
 pub mod family_tree;
+pub mod home;
 
 #[cfg(feature = "ssr")]
 pub mod fileserv;
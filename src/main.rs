@@ -2,8 +2,13 @@
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     use axum::Router;
+    use axum::{
+        routing::{get, post},
+        Extension,
+    };
     use ensab::app::*;
     use ensab::db::get_postgres_pool;
+    use ensab::family_tree::{export_cbor, export_csv, export_gedcom, export_json, import_tree};
     use ensab::fileserv::file_and_error_handler;
     use leptos::{provide_context, *};
     use leptos_axum::{generate_route_list, LeptosRoutes};
@@ -22,6 +27,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // build our application with a route
     let app = Router::new()
+        .route("/export/gedcom/:id", get(export_gedcom))
+        .route("/export/json/:id", get(export_json))
+        .route("/export/cbor/:id", get(export_cbor))
+        .route("/export/csv/:id", get(export_csv))
+        .route("/import/:parent_id", post(import_tree))
+        .layer(Extension(pool.clone()))
         .leptos_routes_with_context(
             &leptos_options,
             routes,